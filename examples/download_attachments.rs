@@ -0,0 +1,51 @@
+//! Downloads all attachments of a Jira issue into the current directory,
+//! printing the progress of each download.
+//!
+//! ```text
+//! JIRA_BASE_URL=https://jira.example.com JIRA_TOKEN=... \
+//!     cargo run --example download_attachments -- PROJ-123
+//! ```
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use jira_downloader::{Auth, DownloadEvent, Jira, download_to_file, format_file_size};
+use tokio::sync::watch;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let issue = std::env::args()
+        .nth(1)
+        .context("Usage: download_attachments <ISSUE>")?;
+    let base_url = std::env::var("JIRA_BASE_URL").context("JIRA_BASE_URL is not set")?;
+    let auth = match (std::env::var("JIRA_USER"), std::env::var("JIRA_TOKEN")) {
+        (Ok(username), token) => Auth::Basic {
+            username,
+            password: token.ok(),
+        },
+        (Err(_), Ok(token)) => Auth::Bearer { token },
+        (Err(_), Err(_)) => Auth::None,
+    };
+
+    let jira = Jira::new(base_url, auth);
+    for att in jira.fetch_attachments(&issue).await? {
+        let (tx, mut rx) = watch::channel(DownloadEvent::Starting);
+        let filename = att.filename.clone();
+
+        // Report progress while the download is running
+        let progress = tokio::spawn(async move {
+            while rx.changed().await.is_ok() {
+                if let DownloadEvent::Progress { downloaded, total } = &*rx.borrow() {
+                    let total = total.map(format_file_size).unwrap_or("?".to_string());
+                    eprint!("\r{filename}: {} / {total}", format_file_size(*downloaded));
+                }
+            }
+            eprintln!();
+        });
+
+        download_to_file(&jira, &att.content, Path::new(&att.filename), tx).await?;
+        progress.await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! Lists the attachments of a Jira issue.
+//!
+//! ```text
+//! JIRA_BASE_URL=https://jira.example.com JIRA_TOKEN=... \
+//!     cargo run --example list_attachments -- PROJ-123
+//! ```
+
+use anyhow::{Context, Result};
+use jira_downloader::{Auth, Jira, format_file_size};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let issue = std::env::args()
+        .nth(1)
+        .context("Usage: list_attachments <ISSUE>")?;
+    let base_url = std::env::var("JIRA_BASE_URL").context("JIRA_BASE_URL is not set")?;
+    let auth = match (std::env::var("JIRA_USER"), std::env::var("JIRA_TOKEN")) {
+        (Ok(username), token) => Auth::Basic {
+            username,
+            password: token.ok(),
+        },
+        (Err(_), Ok(token)) => Auth::Bearer { token },
+        (Err(_), Err(_)) => Auth::None,
+    };
+
+    let jira = Jira::new(base_url, auth);
+    for att in jira.fetch_attachments(&issue).await? {
+        println!(
+            "{:>10}  {}  {}",
+            format_file_size(att.size),
+            att.created,
+            att.filename
+        );
+    }
+
+    Ok(())
+}
@@ -1,44 +1,304 @@
-use crate::jira;
-use crossterm::event::KeyEventKind;
-use futures::{FutureExt, StreamExt, TryFutureExt};
+use crate::{
+    browser::{Browser, BrowserAction, DEFAULT_JQL},
+    extract::{ArchiveKind, extract_archive, extract_dir},
+    goto::{GotoAction, GotoPrompt},
+    help::HelpOverlay,
+    history::{History, Record},
+    hooks::{Hook, HookOutput, run_hooks},
+    keymap::{Action, Key, Keymap, Lookup, Mode},
+    open::{editor_command, open_detached, pager_command},
+    preview::Preview,
+    queue::{QueueEntry, QueueState, SavedQueue},
+    store::ContentStore,
+    theme::Theme,
+};
+use crossterm::event::{KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
+use futures::{FutureExt, StreamExt};
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use globset::{GlobBuilder, GlobSet};
+use jira_downloader::{
+    Compression, RateLimiter, format_file_size,
+    internal::{decompress_to_file, part_path, resume_to_file},
+    jira,
+};
 use ratatui::{
     DefaultTerminal, Frame,
-    layout::{Constraint, Rect},
+    layout::{Constraint, Flex, Position, Rect},
     style::{Modifier, Style},
     widgets::{Block, TableState},
 };
-use std::path::PathBuf;
-use tokio::sync::watch;
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{mpsc, watch},
+    task::JoinHandle,
+};
 use tracing::{debug, error, info};
-use unicode_width::UnicodeWidthStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 /// The main application state and logic.
 #[derive(Debug)]
 pub struct App {
-    issue: String,
-    jira: crate::jira::Jira,
+    jira: jira::Jira,
     table_state: TableState,
-    folder: PathBuf,
+    /// The issues whose attachments have been loaded.
+    issues: Vec<LoadedIssue>,
+    /// The index of the issue whose attachments are shown.
+    current: usize,
+    /// The attachments of all loaded issues.
     attachments: Vec<Attachment>,
-    lengths: (usize, usize, usize, usize),
+    /// The indices of the attachments in the order they are shown.
+    view: Vec<usize>,
+    sort: Option<SortColumn>,
+    sort_descending: bool,
+    /// The text the rows are filtered by.
+    filter: String,
+    /// Whether the filter is being typed.
+    filter_input: bool,
+    fuzzy_filter: bool,
+    /// The pattern of the attachments to queue, while it is being typed.
+    select_input: Option<String>,
     exit: bool,
-    download_ctrl: Option<DownloadCtrl>,
+    download_settings: DownloadSettings,
+    downloads: Vec<DownloadCtrl>,
     status_message: Option<String>,
+    data_dir: PathBuf,
+    history: History,
+    /// Whether to ask before restoring the pending downloads of an earlier
+    /// session.
+    confirm_restore: bool,
+    restore_accepted: bool,
+    queue_changed: bool,
+    task_tx: mpsc::UnboundedSender<TaskEvent>,
+    task_rx: mpsc::UnboundedReceiver<TaskEvent>,
+    ui_settings: UiSettings,
+    /// A message shown in the status area until the next key press.
+    notice: Option<String>,
+    /// A program to run in the terminal, suspending the UI.
+    pending_command: Option<tokio::process::Command>,
+    preview: Option<Preview>,
+    /// Where the table has been drawn, to find the rows clicked with the
+    /// mouse.
+    table_area: Rect,
+    /// The number of rows that fit into the table.
+    page_rows: usize,
+    /// Whether the details of the selected attachment are shown.
+    show_details: bool,
+    /// When and which attachment has been clicked last, to detect double
+    /// clicks.
+    last_click: Option<(Instant, usize)>,
+    /// The projects of the Jira instance, once they have been loaded.
+    projects: Vec<jira::Project>,
+    goto: Option<GotoPrompt>,
+    /// The issue browser, kept while the attachments are shown to return to
+    /// it.
+    browser: Option<Browser>,
+    show_browser: bool,
+    /// The keys of a sequence pressed so far, e.g. the first `g` of `g g`.
+    pending_keys: Vec<Key>,
+    /// The row where visual mode started, queueing the attachments the
+    /// selection moves over.
+    visual_anchor: Option<usize>,
+    help: Option<HelpOverlay>,
+}
+
+/// An issue whose attachments have been loaded.
+#[derive(Debug)]
+struct LoadedIssue {
+    key: String,
+    /// The folder the attachments are downloaded to.
+    folder: PathBuf,
+    /// The pending downloads of an earlier session, until the user has
+    /// decided whether to restore them.
+    saved_queue: Option<SavedQueue>,
+    /// The attachment that was selected when switching to another issue.
+    selected: Option<usize>,
+    /// The widths of the columns.
+    lengths: (usize, usize, usize, usize),
+}
+
+/// Settings controlling how attachments are downloaded.
+#[derive(Debug, Clone)]
+pub struct DownloadSettings {
+    /// Maximum number of concurrent downloads.
+    pub max_downloads: usize,
+    /// Limiter shared by all downloads, adjustable from the UI.
+    pub rate_limiter: RateLimiter,
+    /// Bandwidth limit of each single download in bytes per second.
+    pub download_rate_limit: Option<u64>,
+    /// Store deduplicating the downloaded files, if enabled.
+    pub content_store: Option<ContentStore>,
+    /// Filename patterns of archives to extract after download.
+    pub extract: GlobSet,
+    /// Whether single compressed files are decompressed while downloading.
+    pub decompress: bool,
+    /// Commands to run after matching files have been downloaded.
+    pub hooks: Vec<Hook>,
+}
+
+/// Settings of the user interface.
+#[derive(Debug, Clone)]
+pub struct UiSettings {
+    /// Programs opening downloaded files, by file extension.
+    pub openers: HashMap<String, String>,
+    /// The number of bytes shown in the preview pane.
+    pub preview_size: u64,
+    /// The keys bound to the actions in the attachment table.
+    pub keymap: Keymap,
+    /// The colors and text attributes of the UI.
+    pub theme: Theme,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            openers: HashMap::new(),
+            preview_size: 64 << 10,
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+        }
+    }
 }
 
+/// The narrowest terminal showing the details next to the table.
+const MIN_SIDE_DETAILS_WIDTH: u16 = 120;
+
+/// The height of the status area below the table, including its borders.
+const MIN_STATUS_HEIGHT: u16 = 6;
+
+/// The narrowest the filename column gets on small terminals.
+const MIN_FILENAME_WIDTH: usize = 10;
+
+/// The most issues listed in the issue browser.
+const MAX_SEARCH_RESULTS: usize = 100;
+
+/// The number of recently opened issues offered when going to an issue.
+const RECENT_ISSUES: usize = 20;
+
+/// The longest time between two clicks on a row to count as double click.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
+/// Steps for adjusting the global bandwidth limit from the UI, in bytes/s.
+const RATE_LIMIT_STEPS: &[u64] = &[
+    64 << 10,
+    128 << 10,
+    256 << 10,
+    512 << 10,
+    1 << 20,
+    2 << 20,
+    5 << 20,
+    10 << 20,
+    20 << 20,
+    50 << 20,
+    100 << 20,
+];
+
 #[derive(Debug)]
 struct DownloadCtrl {
     attachment_index: usize,
     progress_rx: watch::Receiver<jira::DownloadEvent>,
+    task: JoinHandle<()>,
+}
+
+/// Events sent by the tasks working on an attachment.
+#[derive(Debug)]
+enum TaskEvent {
+    /// The download or copy of an attachment has ended, with the size of the
+    /// file on success.
+    Downloaded {
+        index: usize,
+        result: Result<u64, String>,
+    },
+    /// The hooks matching a downloaded attachment have been run.
+    HooksRun {
+        index: usize,
+        outputs: Vec<HookOutput>,
+    },
+    /// An archive is being extracted.
+    Extracting {
+        index: usize,
+        extracted: u64,
+        total: u64,
+    },
+    /// The extraction of an archive has ended.
+    Extracted {
+        index: usize,
+        result: Result<(), String>,
+    },
+    /// The beginning of an attachment has been loaded for the preview.
+    Preview {
+        index: usize,
+        result: Result<Vec<u8>, String>,
+    },
+    /// The attachments and the saved queue of an issue have been loaded.
+    IssueLoaded {
+        key: String,
+        result: Result<(Vec<jira::Attachment>, Option<SavedQueue>), String>,
+    },
+    /// The projects of the Jira instance have been loaded.
+    Projects(Vec<jira::Project>),
+    /// The favourite filters of the user have been loaded.
+    Filters(Result<Vec<jira::Filter>, String>),
+    /// The issues matching a JQL query have been found.
+    SearchResults {
+        jql: String,
+        result: Result<Vec<jira::IssueSummary>, String>,
+    },
+}
+
+/// Everything a download task needs to know about its attachment.
+#[derive(Debug, Clone)]
+pub(crate) struct DownloadJob {
+    pub issue: String,
+    pub attachment_id: String,
+    pub url: String,
+    pub file_path: PathBuf,
+    /// The filename of the attachment in Jira.
+    pub filename: String,
+    /// The compressed size of the attachment, as reported by Jira.
+    pub size: u64,
+    /// The compression format to decompress while downloading, if any.
+    pub compression: Option<Compression>,
+}
+
+/// A column the attachment table can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortColumn {
+    State,
+    Filename,
+    Size,
+    Created,
 }
 
 #[derive(Debug, Clone)]
 struct Attachment {
+    /// The index of the issue the attachment belongs to.
+    issue: usize,
+    id: String,
     filename: String,
-    size: usize,
+    size: u64,
     created: String,
+    created_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    author: Option<String>,
+    mime_type: Option<String>,
     state: AttachmentState,
     content: String,
+    /// Whether the archive is extracted once it has been downloaded.
+    extract: bool,
+    /// The compression format to decompress while downloading, if any.
+    compression: Option<Compression>,
+    /// The outcome of the hooks run after the last download.
+    hook_outputs: Vec<HookOutput>,
+    /// The downloaded file and its size, known once the download has
+    /// completed.
+    on_disk: Option<(PathBuf, u64)>,
+    /// The earlier download to copy, or to link if set, instead of
+    /// downloading the queued attachment again.
+    copy_from: Option<(Box<Record>, bool)>,
 }
 
 /// The state of an attachment in the download process.
@@ -50,39 +310,124 @@ pub enum AttachmentState {
     Queued,
     /// The attachment is currently being downloaded.
     Downloading { downloaded: u64, total: Option<u64> },
+    /// The download has been paused, keeping the partially downloaded file.
+    Paused { downloaded: u64, total: Option<u64> },
     /// The attachment has been downloaded.
     Downloaded,
+    /// The attachment has been downloaded to another folder before.
+    Available { record: Box<Record> },
     /// The attachment failed to download.
     Failed { errmsg: String },
+    /// The downloaded archive is being extracted.
+    Extracting { extracted: u64, total: u64 },
+    /// The downloaded archive has been extracted.
+    Extracted,
+    /// The attachment has been downloaded, but extracting it failed.
+    ExtractionFailed { errmsg: String },
 }
 
 impl App {
     /// Creates a new App instance.
     pub fn new(
-        jira: crate::jira::Jira,
+        jira: jira::Jira,
         issue: String,
         folder: PathBuf,
-        attachments: Vec<crate::jira::Attachment>,
+        attachments: Vec<jira::Attachment>,
+        download_settings: DownloadSettings,
+        data_dir: PathBuf,
+        history: History,
     ) -> Self {
-        let attachments: Vec<Attachment> = attachments.into_iter().map(Attachment::from).collect();
+        let (task_tx, task_rx) = mpsc::unbounded_channel();
+
+        let mut app = Self {
+            jira,
+            table_state: TableState::default(),
+            issues: Vec::new(),
+            current: 0,
+            attachments: Vec::new(),
+            view: Vec::new(),
+            sort: None,
+            sort_descending: false,
+            filter: String::new(),
+            filter_input: false,
+            fuzzy_filter: false,
+            select_input: None,
+            exit: false,
+            download_settings: DownloadSettings {
+                max_downloads: download_settings.max_downloads.max(1),
+                ..download_settings
+            },
+            downloads: Vec::new(),
+            status_message: None,
+            data_dir,
+            history,
+            confirm_restore: false,
+            restore_accepted: false,
+            queue_changed: false,
+            task_tx,
+            task_rx,
+            ui_settings: UiSettings::default(),
+            notice: None,
+            pending_command: None,
+            preview: None,
+            table_area: Rect::default(),
+            page_rows: 1,
+            show_details: false,
+            last_click: None,
+            projects: Vec::new(),
+            goto: None,
+            browser: None,
+            show_browser: false,
+            pending_keys: Vec::new(),
+            visual_anchor: None,
+            help: None,
+        };
+        app.add_issue(issue, folder, attachments);
+        app.view = (0..app.attachments.len()).collect();
+        app
+    }
 
+    /// Adds the attachments of an issue to download into `folder`, shown in
+    /// a tab of its own, returning the index of the issue.
+    pub fn add_issue(
+        &mut self,
+        key: String,
+        folder: PathBuf,
+        attachments: Vec<jira::Attachment>,
+    ) -> usize {
+        let index = self.issues.len();
+        let attachments: Vec<Attachment> = attachments
+            .into_iter()
+            .map(Attachment::from)
+            .map(|att| Attachment {
+                issue: index,
+                compression: Compression::from_filename(&att.filename)
+                    .filter(|_| self.download_settings.decompress),
+                ..att
+            })
+            .collect();
+
+        // leave room for the sort indicator in the headers
         let max_filename_width = attachments
             .iter()
             .map(|att| att.filename.width())
             .max()
-            .unwrap_or(0);
+            .unwrap_or(0)
+            .max("Filename ▲".width());
 
         let max_size_width = attachments
             .iter()
             .map(|att| format_file_size(att.size).width())
             .max()
-            .unwrap_or(0);
+            .unwrap_or(0)
+            .max("Size ▲".width());
 
         let max_created_width = attachments
             .iter()
             .map(|att| att.created.width())
             .max()
-            .unwrap_or(0);
+            .unwrap_or(0)
+            .max("Created ▲".width());
 
         let lengths = (
             4, // State column width
@@ -91,80 +436,116 @@ impl App {
             max_created_width,
         );
 
-        Self {
-            issue,
-            jira,
-            table_state: TableState::default(),
+        self.attachments.extend(attachments);
+        self.issues.push(LoadedIssue {
+            key,
             folder,
-            attachments,
+            saved_queue: None,
+            selected: None,
             lengths,
-            exit: false,
-            download_ctrl: None,
-            status_message: None,
-        }
+        });
+        index
+    }
+
+    /// Sets the settings of the user interface.
+    pub fn with_ui_settings(mut self, ui_settings: UiSettings) -> Self {
+        self.ui_settings = ui_settings;
+        self
+    }
+
+    /// Restores the pending downloads of an earlier session when the
+    /// application is run, asking the user first if `confirm` is set.
+    pub fn restore_queue(&mut self, saved_queue: SavedQueue, confirm: bool) {
+        let Some(issue) = self
+            .issues
+            .iter_mut()
+            .find(|issue| issue.key == saved_queue.issue)
+        else {
+            return;
+        };
+        issue.saved_queue = Some(saved_queue);
+        self.confirm_restore = confirm;
     }
 
     /// Runs the main application loop.
     pub async fn run(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
-        if let Err(err) = tokio::fs::create_dir_all(&self.folder).await {
-            return Err(anyhow::anyhow!(
-                "Failed to create download directory {:?}: {}",
-                self.folder,
-                err
-            ));
-        }
+        crossterm::execute!(std::io::stdout(), crossterm::event::EnableMouseCapture)?;
+        let result = self.run_loop(terminal).await;
+        crossterm::execute!(std::io::stdout(), crossterm::event::DisableMouseCapture)?;
+        result
+    }
 
-        // Init attachement state
-        for att in self.attachments.iter_mut() {
-            att.state = match tokio::fs::try_exists(self.folder.join(&att.filename)).await {
-                Ok(true) => AttachmentState::Downloaded,
-                Ok(false) => AttachmentState::NotDownloaded,
-                Err(e) => AttachmentState::Failed {
-                    errmsg: e.to_string(),
-                },
-            };
+    async fn run_loop(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+        for issue in 0..self.issues.len() {
+            let folder = &self.issues[issue].folder;
+            if let Err(err) = tokio::fs::create_dir_all(folder).await {
+                return Err(anyhow::anyhow!(
+                    "Failed to create download directory {:?}: {}",
+                    folder,
+                    err
+                ));
+            }
+
+            self.init_states(issue).await;
+            if !self.confirm_restore
+                && let Some(saved_queue) = self.issues[issue].saved_queue.take()
+            {
+                self.apply_saved_queue(issue, saved_queue).await;
+            }
         }
+        self.load_projects();
 
         // Main loop
         while !self.exit {
             let min_delay = tokio::time::sleep(std::time::Duration::from_millis(20));
 
+            self.update_view();
             self.update_status_message();
             terminal.draw(|frame| {
                 self.draw(frame);
             })?;
 
             let mut evt_reader = crossterm::event::EventStream::new();
-            let progress_fut =
-                self.download_ctrl
-                    .as_mut()
-                    .map_or(futures::future::pending().boxed(), |ctrl| {
-                        async move {
-                            if let Err(err) = ctrl.progress_rx.changed().await {
-                                (
-                                    ctrl.attachment_index,
-                                    jira::DownloadEvent::Error {
-                                        msg: err.to_string(),
-                                    },
-                                )
-                            } else {
-                                (ctrl.attachment_index, ctrl.progress_rx.borrow().clone())
-                            }
+            let progress_fut = if self.downloads.is_empty() {
+                futures::future::pending().boxed()
+            } else {
+                // wait for the next event of any of the active downloads
+                futures::future::select_all(self.downloads.iter_mut().map(|ctrl| {
+                    async move {
+                        if ctrl.progress_rx.changed().await.is_err() {
+                            // the task has ended and reports its result as a
+                            // task event
+                            futures::future::pending().await
+                        } else {
+                            (ctrl.attachment_index, ctrl.progress_rx.borrow().clone())
                         }
-                        .boxed()
-                    });
+                    }
+                    .boxed()
+                }))
+                .map(|(evt, _, _)| evt)
+                .boxed()
+            };
 
             tokio::select! {
                 (index, evt) = progress_fut => {
                     self.update_download(index, evt);
                 }
+                Some(evt) = self.task_rx.recv() => {
+                    self.handle_task_event(evt).await;
+                    while let Ok(evt) = self.task_rx.try_recv() {
+                        self.handle_task_event(evt).await;
+                    }
+                }
                 maybe_evt = evt_reader.next() => {
                     match maybe_evt {
                         Some(Ok(evt)) => match evt {
                             crossterm::event::Event::Key(key_evt)
                                 if key_evt.kind == KeyEventKind::Press =>
                             {
-                                self.handle_key_press(key_evt);
+                                self.handle_key_press(key_evt).await;
+                            }
+                            crossterm::event::Event::Mouse(mouse_evt) => {
+                                self.handle_mouse(mouse_evt);
                             }
                             _ => {}
                         },
@@ -176,300 +557,2610 @@ impl App {
                 }
             }
 
-            min_delay.await;
-        }
-
-        Ok(())
-    }
-
-    fn handle_key_press(&mut self, key_evt: crossterm::event::KeyEvent) {
-        match key_evt.code {
-            crossterm::event::KeyCode::Char('q') => {
-                self.exit = true;
-            }
-            crossterm::event::KeyCode::Up => {
-                self.previous_row();
-            }
-            crossterm::event::KeyCode::Down => {
-                self.next_row();
-            }
-            crossterm::event::KeyCode::Char(' ') => {
-                self.toggle_selection();
-            }
-            crossterm::event::KeyCode::Enter => {
-                self.start_downloads();
+            if self.restore_accepted {
+                self.restore_accepted = false;
+                if let Some(saved_queue) = self.issues[self.current].saved_queue.take() {
+                    self.apply_saved_queue(self.current, saved_queue).await;
+                }
             }
-            crossterm::event::KeyCode::Esc => {
-                self.table_state.select(None);
+
+            if let Some(command) = self.pending_command.take() {
+                // stop reading events, the program needs the terminal input
+                drop(evt_reader);
+                self.run_in_terminal(terminal, command).await?;
             }
-            crossterm::event::KeyCode::Tab => {
-                self.table_state
-                    .select(self.table_state.selected().map_or(Some(0), |_| None));
+
+            if self.queue_changed {
+                self.queue_changed = false;
+                self.save_queue().await;
             }
-            _ => {}
-        }
-    }
 
-    fn next_row(&mut self) {
-        self.table_state.select(Some(
-            self.table_state
-                .selected()
-                .map(|i| std::cmp::min(i + 1, self.attachments.len() - 1))
-                .unwrap_or(0),
-        ));
-    }
+            min_delay.await;
+        }
 
-    fn previous_row(&mut self) {
-        self.table_state.select(Some(
-            self.table_state
-                .selected()
-                .map(|i| i.saturating_sub(1))
-                .unwrap_or(0),
-        ));
-    }
+        // Record the progress of unfinished downloads
+        self.save_queue().await;
 
-    fn toggle_selection(&mut self) {
-        if let Some(selected) = self.table_state.selected() {
-            let att = &mut self.attachments[selected];
-            att.state = match att.state {
-                AttachmentState::NotDownloaded | AttachmentState::Failed { errmsg: _ } => {
-                    AttachmentState::Queued
-                }
-                AttachmentState::Queued => AttachmentState::NotDownloaded,
-                ref state => state.clone(),
-            };
-        }
+        Ok(())
     }
 
-    fn update_status_message(&mut self) {
-        if let Some(i) = self.table_state.selected() {
-            let att = &self.attachments[i];
-            self.status_message = match &att.state {
-                AttachmentState::NotDownloaded => {
-                    Some(format!("Attachment '{}' is not downloaded.", att.filename))
-                }
-                AttachmentState::Queued => Some(format!(
-                    "Attachment '{}' is queued for download.",
-                    att.filename
-                )),
-                AttachmentState::Downloading { downloaded, total } => {
-                    if let Some(total) = total {
-                        Some(format!(
-                            "Downloading '{}'... {}/{} bytes",
-                            att.filename, downloaded, total
-                        ))
+    /// Initializes the state of the attachments of an issue from the files
+    /// in its download folder and the download history.
+    async fn init_states(&mut self, issue: usize) {
+        let instance = self.jira.base_url();
+        let folder = &self.issues[issue].folder;
+        for att in self.attachments.iter_mut().filter(|att| att.issue == issue) {
+            let file_path = folder.join(att.local_filename());
+            att.state = match tokio::fs::metadata(&file_path).await {
+                Ok(metadata) => {
+                    att.on_disk = Some((file_path.clone(), metadata.len()));
+                    if ArchiveKind::from_filename(att.local_filename()).is_some()
+                        && extract_dir(&file_path).is_dir()
+                    {
+                        AttachmentState::Extracted
                     } else {
-                        Some(format!(
-                            "Downloading '{}'... {} bytes downloaded",
-                            att.filename, downloaded
-                        ))
+                        AttachmentState::Downloaded
                     }
                 }
-                AttachmentState::Downloaded => Some(format!(
-                    "Attachment '{}' has been downloaded.",
-                    att.filename
-                )),
-                AttachmentState::Failed { errmsg } => Some(format!(
-                    "Attachment '{}' failed to download: {}",
-                    att.filename, errmsg
-                )),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    match tokio::fs::metadata(part_path(&file_path)).await {
+                        // a download paused in an earlier session; decompressed
+                        // downloads start over, their part file not matching
+                        // the size of the attachment
+                        Ok(metadata) => AttachmentState::Paused {
+                            downloaded: if att.compression.is_some() {
+                                0
+                            } else {
+                                metadata.len()
+                            },
+                            total: Some(att.size),
+                        },
+                        Err(_) => match self.history.find_existing(instance, &att.id) {
+                            Ok(Some(record)) => AttachmentState::Available {
+                                record: Box::new(record),
+                            },
+                            Ok(None) => AttachmentState::NotDownloaded,
+                            Err(e) => {
+                                error!("Failed to look up download history: {}", e);
+                                AttachmentState::NotDownloaded
+                            }
+                        },
+                    }
+                }
+                Err(e) => AttachmentState::Failed {
+                    errmsg: e.to_string(),
+                },
             };
         }
     }
 
-    fn draw(&mut self, frame: &mut Frame) {
-        let toplayout =
-            ratatui::layout::Layout::vertical([Constraint::Fill(1), Constraint::Max(1)])
-                .split(frame.area());
-
-        let layout = ratatui::layout::Layout::vertical([
-            Constraint::Max(self.attachments.len() as u16 + 5),
-            Constraint::Fill(1),
-        ])
-        .spacing(ratatui::layout::Spacing::Overlap(1))
-        .split(toplayout[0]);
-
-        self.render_table(frame, layout[0]);
-        self.render_status(frame, layout[1]);
-        self.render_help(frame, toplayout[1]);
-    }
-
-    fn render_table(&mut self, frame: &mut Frame, area: Rect) {
-        let rows = self.attachments.iter().map(|att| {
-            ratatui::widgets::Row::new(vec![
-                ratatui::text::Line::from(att.state.to_string()).right_aligned(),
-                att.filename.clone().into(),
-                format_file_size(att.size).into(),
-                att.created.clone().into(),
-            ])
-        });
-
-        let selected_row_style = Style::default().add_modifier(Modifier::REVERSED);
+    /// Restores the pending downloads of an issue from an earlier session.
+    async fn apply_saved_queue(&mut self, issue: usize, saved_queue: SavedQueue) {
+        info!(
+            "Restoring {} pending downloads of {}",
+            saved_queue.entries.len(),
+            saved_queue.issue
+        );
 
-        let t = ratatui::widgets::Table::new(
-            rows,
-            [
-                Constraint::Max(self.lengths.0 as u16),
-                Constraint::Max(self.lengths.1 as u16 + 1),
-                Constraint::Max(self.lengths.2 as u16 + 1),
-                Constraint::Max(self.lengths.3 as u16),
-            ],
-        )
-        .header(
-            ratatui::widgets::Row::new(vec!["", "Filename", "Size", "Created"])
-                .style(ratatui::style::Style::default().fg(ratatui::style::Color::Yellow))
-                .bottom_margin(1),
-        )
-        .block(
-            ratatui::widgets::Block::default()
-                .title(format!("{} Attachments", self.issue))
-                .borders(ratatui::widgets::Borders::ALL)
-                .merge_borders(ratatui::symbols::merge::MergeStrategy::Exact),
-        )
-        .row_highlight_style(selected_row_style);
+        if saved_queue.folder != self.issues[issue].folder {
+            // the downloads were started from another directory
+            let folder = &mut self.issues[issue].folder;
+            *folder = saved_queue.folder;
+            if let Err(e) = tokio::fs::create_dir_all(&folder).await {
+                error!("Failed to create download directory {:?}: {}", folder, e);
+            }
+            self.init_states(issue).await;
+        }
 
-        frame.render_stateful_widget(t, area, &mut self.table_state);
-    }
+        let mut resume = false;
+        for entry in saved_queue.entries {
+            let Some(att) = self
+                .attachments
+                .iter_mut()
+                .find(|att| att.issue == issue && att.id == entry.id)
+            else {
+                debug!("Attachment {} no longer exists", entry.filename);
+                continue;
+            };
+            att.state = match (entry.state, &att.state) {
+                // the file has been downloaded since the queue was saved,
+                // here or, for available ones, into another folder to copy
+                // it from, and paused ones continue from their part file
+                (
+                    _,
+                    AttachmentState::Downloaded
+                    | AttachmentState::Extracting { .. }
+                    | AttachmentState::Extracted
+                    | AttachmentState::ExtractionFailed { .. }
+                    | AttachmentState::Available { .. }
+                    | AttachmentState::Paused { .. },
+                ) => continue,
+                (QueueState::Paused, _) => AttachmentState::Paused {
+                    downloaded: 0,
+                    total: Some(att.size),
+                },
+                (QueueState::Queued, _) => AttachmentState::Queued,
+                (QueueState::Downloading, _) => {
+                    resume = true;
+                    AttachmentState::Queued
+                }
+            };
+        }
 
-    fn render_status(&self, frame: &mut Frame, area: Rect) {
-        let paragraph =
-            ratatui::widgets::Paragraph::new(self.status_message.clone().unwrap_or_default())
-                .block(
-                    Block::bordered().merge_borders(ratatui::symbols::merge::MergeStrategy::Exact),
-                );
-        frame.render_widget(paragraph, area);
+        // paused entries with a part file have been initialized as paused
+        // already, queued ones continue from their part file when started
+        self.queue_changed = true;
+        if resume {
+            self.start_downloads();
+        }
     }
 
-    fn render_help(&self, frame: &mut Frame, area: Rect) {
-        let status_text =
-            "q: Quit | ↑/↓: Navigate | Space: Select/Deselect | Enter: Start Download";
-        let paragraph = ratatui::widgets::Paragraph::new(status_text)
-            .style(Style::default().add_modifier(Modifier::REVERSED));
-        frame.render_widget(paragraph, area);
+    /// Saves the pending downloads of all loaded issues, so they can be
+    /// restored in a later session.
+    async fn save_queue(&self) {
+        for issue in 0..self.issues.len() {
+            self.save_issue_queue(issue).await;
+        }
     }
 
-    fn start_downloads(&mut self) {
-        if self.download_ctrl.is_some() {
-            // download already in progress
+    async fn save_issue_queue(&self, issue: usize) {
+        let loaded = &self.issues[issue];
+        if loaded.saved_queue.is_some() {
+            // the user has not decided yet whether to restore the saved queue
             return;
         }
 
-        if let Some((i, a)) = self
+        let entries = self
             .attachments
             .iter()
-            .enumerate()
-            .find(|(_, a)| a.state == AttachmentState::Queued)
-        {
-            let j = self.jira.clone();
-            let url = a.content.clone();
-            let file_path = self.folder.join(&a.filename);
-            let (tx, rx) = watch::channel(jira::DownloadEvent::Starting);
-
-            // spawn a tokio task to download
-            tokio::spawn(async move {
-                if let Err(e) = download_attachment(&j, url, file_path, tx.clone()).await {
-                    let _ = tx.send(jira::DownloadEvent::Error { msg: e.to_string() });
-                }
-            });
+            .filter(|att| att.issue == issue)
+            .filter_map(|att| {
+                let (state, downloaded) = match att.state {
+                    AttachmentState::Queued => (QueueState::Queued, 0),
+                    AttachmentState::Downloading { downloaded, .. } => {
+                        (QueueState::Downloading, downloaded)
+                    }
+                    AttachmentState::Paused { downloaded, .. } => (QueueState::Paused, downloaded),
+                    _ => return None,
+                };
+                Some(QueueEntry {
+                    id: att.id.clone(),
+                    filename: att.filename.clone(),
+                    url: att.content.clone(),
+                    path: loaded.folder.join(att.local_filename()),
+                    size: att.size,
+                    downloaded,
+                    state,
+                })
+            })
+            .collect();
 
-            self.download_ctrl = Some(DownloadCtrl {
-                attachment_index: i,
-                progress_rx: rx,
-            });
+        let queue = SavedQueue {
+            base_url: self.jira.base_url().to_string(),
+            issue: loaded.key.clone(),
+            folder: loaded.folder.clone(),
+            entries,
         };
+        if let Err(e) = queue.save(&self.data_dir).await {
+            error!("Failed to save download queue: {}", e);
+        }
     }
 
-    fn update_download(&mut self, index: usize, evt: jira::DownloadEvent) {
-        let att = &mut self.attachments[index];
-        match evt {
-            jira::DownloadEvent::Starting => {
-                debug!("Starting download for {}", att.filename);
-                att.state = AttachmentState::Downloading {
-                    downloaded: 0,
-                    total: None,
-                };
-            }
-            jira::DownloadEvent::Progress { downloaded, total } => {
-                att.state = AttachmentState::Downloading { downloaded, total };
-            }
-            jira::DownloadEvent::Finished => {
-                info!("Download finished for {}", att.filename);
-                att.state = AttachmentState::Downloaded;
-                self.download_ctrl = None;
-                self.start_downloads(); // start next download
+    /// Runs a program taking over the terminal, like a pager, and restores
+    /// the user interface once it exits.
+    async fn run_in_terminal(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        mut command: tokio::process::Command,
+    ) -> anyhow::Result<()> {
+        let program = command
+            .as_std()
+            .get_program()
+            .to_string_lossy()
+            .into_owned();
+        debug!("Running {:?}", command);
+
+        crossterm::execute!(std::io::stdout(), crossterm::event::DisableMouseCapture)?;
+        ratatui::restore();
+        let status = command.status().await;
+        crossterm::terminal::enable_raw_mode()?;
+        crossterm::execute!(
+            std::io::stdout(),
+            crossterm::terminal::EnterAlternateScreen,
+            crossterm::event::EnableMouseCapture
+        )?;
+        terminal.clear()?;
+
+        match status {
+            Ok(status) if !status.success() => {
+                self.notice = Some(format!("{} exited with {}", program, status));
             }
-            jira::DownloadEvent::Error { msg } => {
-                error!("Download error for {}: {}", att.filename, msg);
-                att.state = AttachmentState::Failed { errmsg: msg };
-                self.download_ctrl = None;
-                self.start_downloads(); // start next download
+            Ok(_) => {}
+            Err(e) => {
+                error!("Failed to run {}: {}", program, e);
+                self.notice = Some(format!("Failed to run {}: {}", program, e));
             }
         }
+        Ok(())
     }
-}
 
-async fn create_tmp_download_file(
-    file_path: &PathBuf,
-) -> anyhow::Result<(tokio::fs::File, PathBuf)> {
-    let mut tmp_file_path = file_path.clone();
-    loop {
-        tmp_file_path.add_extension("part");
-        match tokio::fs::File::create_new(&tmp_file_path).await {
-            Ok(file) => break Ok((file, tmp_file_path)),
-            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
-                // try again with a new name
-                continue;
+    async fn handle_key_press(&mut self, key_evt: crossterm::event::KeyEvent) {
+        self.notice = None;
+        if self.help.is_some() {
+            if let Some(action) = self.lookup_keys(Mode::Help, key_evt)
+                && let Some(help) = &mut self.help
+                && !help.perform(action)
+            {
+                self.help = None;
             }
-            Err(e) => {
-                break Err(anyhow::anyhow!(
-                    "Failed to create file {:?}: {}",
-                    tmp_file_path,
-                    e
-                ));
+            return;
+        }
+        if let Some(preview) = &mut self.preview
+            && preview.is_searching()
+        {
+            preview.handle_search_key(key_evt);
+            return;
+        }
+        if self.preview.is_some() {
+            if let Some(action) = self.lookup_keys(Mode::Preview, key_evt)
+                && let Some(preview) = &mut self.preview
+                && !preview.perform(action)
+            {
+                self.preview = None;
             }
+            return;
+        }
+        if self.filter_input {
+            self.handle_filter_key(key_evt);
+            return;
+        }
+        if self.select_input.is_some() {
+            self.handle_select_key(key_evt);
+            return;
+        }
+        if self.show_browser
+            && let Some(browser) = &mut self.browser
+        {
+            let browser_action = if browser.is_editing() {
+                browser.handle_input_key(key_evt)
+            } else if let Some(action) = self.lookup_keys(Mode::Browser, key_evt)
+                && let Some(browser) = &mut self.browser
+            {
+                browser.perform(action)
+            } else {
+                BrowserAction::None
+            };
+            match browser_action {
+                BrowserAction::None => {}
+                BrowserAction::Close => self.show_browser = false,
+                BrowserAction::Search(jql) => self.search_issues(jql),
+                BrowserAction::Open(key) => {
+                    self.show_browser = false;
+                    self.open_issue(key);
+                }
+            }
+            return;
+        }
+        if let Some(goto) = &mut self.goto {
+            match goto.handle_key(key_evt) {
+                GotoAction::Continue => {}
+                GotoAction::Close => self.goto = None,
+                GotoAction::Open(key) => {
+                    self.goto = None;
+                    self.open_issue(key);
+                }
+            }
+            return;
+        }
+        if self.confirm_restore && self.saved_queue().is_some() {
+            match self.lookup_keys(Mode::Prompt, key_evt) {
+                Some(Action::RestoreQueue) => {
+                    self.restore_accepted = true;
+                }
+                Some(Action::DiscardQueue) => {
+                    info!("Discarding pending downloads of the last session");
+                    self.issues[self.current].saved_queue = None;
+                    self.queue_changed = true;
+                }
+                Some(Action::Quit) => {
+                    self.exit = true;
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(action) = self.lookup_keys(Mode::Normal, key_evt) {
+            self.perform(action).await;
+        }
+    }
+
+    /// Adds a key press to the keys pressed so far and looks them up in
+    /// `mode`, returning the action once they are bound to one.
+    fn lookup_keys(&mut self, mode: Mode, key_evt: crossterm::event::KeyEvent) -> Option<Action> {
+        self.pending_keys.push(key_evt.into());
+        let mut lookup = self.ui_settings.keymap.lookup(mode, &self.pending_keys);
+        if lookup == Lookup::None && self.pending_keys.len() > 1 {
+            // The sequence has been abandoned, the key may start another one.
+            self.pending_keys = vec![key_evt.into()];
+            lookup = self.ui_settings.keymap.lookup(mode, &self.pending_keys);
+        }
+        match lookup {
+            Lookup::Pending => None,
+            Lookup::Action(action) => {
+                self.pending_keys.clear();
+                Some(action)
+            }
+            Lookup::None => {
+                self.pending_keys.clear();
+                None
+            }
+        }
+    }
+
+    /// Performs an action bound to the keys pressed.
+    async fn perform(&mut self, action: Action) {
+        match action {
+            Action::Quit => {
+                self.exit = true;
+            }
+            Action::ExtendUp => {
+                self.extend_selection(false);
+            }
+            Action::ExtendDown => {
+                self.extend_selection(true);
+            }
+            Action::Up => {
+                self.previous_row();
+            }
+            Action::Down => {
+                self.next_row();
+            }
+            Action::PageUp => {
+                self.select_row(|row, page| row.saturating_sub(page));
+            }
+            Action::PageDown => {
+                self.select_row(|row, page| row + page);
+            }
+            Action::First => {
+                self.select_row(|_, _| 0);
+            }
+            Action::Last => {
+                self.select_row(|_, _| usize::MAX);
+            }
+            Action::Visual => {
+                self.toggle_visual();
+            }
+            Action::ToggleQueued => {
+                self.toggle_selection();
+            }
+            Action::SelectAll => {
+                self.select_all(true);
+            }
+            Action::SelectNone => {
+                self.select_all(false);
+            }
+            Action::InvertSelection => {
+                self.invert_selection();
+            }
+            Action::SelectMatching => {
+                self.select_input = Some(String::new());
+            }
+            Action::StartDownloads => {
+                self.start_downloads();
+            }
+            Action::Pause => {
+                self.toggle_pause().await;
+            }
+            Action::Cancel => {
+                self.cancel_download();
+            }
+            Action::CopyFromHistory => {
+                self.copy_from_history(false);
+            }
+            Action::LinkFromHistory => {
+                self.copy_from_history(true);
+            }
+            Action::Extract => {
+                self.extract_selected();
+            }
+            Action::OpenFile => {
+                self.open_selected();
+            }
+            Action::OpenFolder => {
+                self.open_folder();
+            }
+            Action::Edit => {
+                self.open_in_terminal(editor_command);
+            }
+            Action::View => {
+                self.open_in_terminal(pager_command);
+            }
+            Action::Preview => {
+                self.open_preview();
+            }
+            Action::Details => {
+                self.show_details = !self.show_details;
+            }
+            Action::GotoIssue => {
+                self.open_goto_prompt();
+            }
+            Action::BrowseIssues => {
+                self.open_browser();
+            }
+            Action::PreviousIssue => {
+                self.switch_issue(self.current.checked_sub(1).unwrap_or(self.issues.len() - 1));
+            }
+            Action::NextIssue => {
+                self.switch_issue((self.current + 1) % self.issues.len());
+            }
+            Action::Sort => {
+                self.cycle_sort_column();
+            }
+            Action::ReverseSort => {
+                self.sort_descending = !self.sort_descending;
+            }
+            Action::RaiseRateLimit => {
+                self.change_rate_limit(true);
+            }
+            Action::LowerRateLimit => {
+                self.change_rate_limit(false);
+            }
+            Action::Filter => {
+                self.filter_input = true;
+            }
+            Action::Back if self.visual_anchor.is_some() => {
+                self.visual_anchor = None;
+            }
+            Action::Back if !self.filter.is_empty() => {
+                self.filter.clear();
+            }
+            Action::Back => {
+                self.table_state.select(None);
+            }
+            Action::ToggleSelection => {
+                self.table_state
+                    .select(self.table_state.selected().map_or(Some(0), |_| None));
+            }
+            Action::Help => {
+                self.help = Some(HelpOverlay::new(&self.ui_settings.keymap));
+            }
+            Action::SwitchIssue(n) => {
+                let index = n as usize - 1;
+                if index < self.issues.len() {
+                    self.switch_issue(index);
+                }
+            }
+            // handled by the prompt and popups they are bound in
+            Action::RestoreQueue
+            | Action::DiscardQueue
+            | Action::PreviewClose
+            | Action::PreviewUp
+            | Action::PreviewDown
+            | Action::PreviewPageUp
+            | Action::PreviewPageDown
+            | Action::PreviewTop
+            | Action::PreviewBottom
+            | Action::PreviewSearch
+            | Action::PreviewNextMatch
+            | Action::PreviewPreviousMatch
+            | Action::BrowserClose
+            | Action::BrowserUp
+            | Action::BrowserDown
+            | Action::BrowserSwitchPane
+            | Action::BrowserEditQuery
+            | Action::BrowserOpen
+            | Action::HelpClose
+            | Action::HelpUp
+            | Action::HelpDown
+            | Action::HelpPageUp
+            | Action::HelpPageDown
+            | Action::HelpTop
+            | Action::HelpBottom => {}
+        }
+        match action {
+            Action::Up
+            | Action::Down
+            | Action::PageUp
+            | Action::PageDown
+            | Action::First
+            | Action::Last
+            | Action::Visual => {
+                if let Some(anchor) = self.visual_anchor {
+                    self.queue_visual_range(anchor);
+                }
+            }
+            // The rows move, so the range would no longer be what the user
+            // has seen.
+            Action::Sort | Action::ReverseSort | Action::Filter => self.visual_anchor = None,
+            _ => {}
+        }
+    }
+
+    /// Starts or ends queueing the attachments the selection moves over.
+    fn toggle_visual(&mut self) {
+        if self.visual_anchor.take().is_some() {
+            return;
+        }
+        if self.table_state.selected().is_none() && !self.view.is_empty() {
+            self.table_state.select(Some(0));
+        }
+        self.visual_anchor = self.table_state.selected();
+    }
+
+    /// Queues the attachments between the row where visual mode started and
+    /// the selected one.
+    fn queue_visual_range(&mut self, anchor: usize) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let range = anchor.min(selected)..=anchor.max(selected);
+        for row in range {
+            if let Some(&index) = self.view.get(row) {
+                self.set_queued(index, true);
+            }
+        }
+    }
+
+    /// Handles clicks and the mouse wheel.
+    ///
+    /// A click selects a row, a double click or a click into the state
+    /// column queues or unqueues it, and a click on a column header sorts by
+    /// it.
+    fn handle_mouse(&mut self, mouse_evt: MouseEvent) {
+        if self.select_input.is_some()
+            || self.goto.is_some()
+            || self.help.is_some()
+            || self.show_browser
+            || self.saved_queue().is_some()
+        {
+            return;
+        }
+        let position = Position::new(mouse_evt.column, mouse_evt.row);
+        match mouse_evt.kind {
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                let down = mouse_evt.kind == MouseEventKind::ScrollDown;
+                match &mut self.preview {
+                    Some(preview) if !self.table_area.contains(position) => {
+                        preview.scroll_by(down);
+                    }
+                    _ if down => self.next_row(),
+                    _ => self.previous_row(),
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.notice = None;
+                let Some(column) = self.column_at(position.x) else {
+                    return;
+                };
+                if position.y == self.table_area.y + 1 {
+                    self.sort_by_column(column);
+                } else if let Some(row) = self.row_at(position.y) {
+                    self.table_state.select(Some(row));
+                    let index = self.view[row];
+                    let now = Instant::now();
+                    let double_click = self.last_click.is_some_and(|(time, last)| {
+                        last == index && now.duration_since(time) < DOUBLE_CLICK_TIME
+                    });
+                    if double_click || column == SortColumn::State {
+                        self.toggle_selection();
+                        self.last_click = None;
+                    } else {
+                        self.last_click = Some((now, index));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the table row at the line `y` of the terminal.
+    fn row_at(&self, y: u16) -> Option<usize> {
+        // below the top border, the header and its margin
+        let first = self.table_area.y + 3;
+        if y < first || y + 1 >= self.table_area.bottom() {
+            return None;
+        }
+        let row = self.table_state.offset() + (y - first) as usize;
+        (row < self.view.len()).then_some(row)
+    }
+
+    /// Returns the table column at the column `x` of the terminal.
+    fn column_at(&self, x: u16) -> Option<SortColumn> {
+        let inner = self.table_area.inner(ratatui::layout::Margin::new(1, 1));
+        let areas = ratatui::layout::Layout::horizontal(self.column_widths(inner.width))
+            .flex(Flex::Start)
+            .spacing(1)
+            .split(inner);
+        let columns = [
+            SortColumn::State,
+            SortColumn::Filename,
+            SortColumn::Size,
+            SortColumn::Created,
+        ];
+        areas
+            .iter()
+            .zip(columns)
+            .find(|(area, _)| area.left() <= x && x < area.right())
+            .map(|(_, column)| column)
+    }
+
+    /// Sorts by `column`, or reverses the order if the table is sorted by it
+    /// already.
+    fn sort_by_column(&mut self, column: SortColumn) {
+        if self.sort == Some(column) {
+            self.sort_descending = !self.sort_descending;
+        } else {
+            self.sort = Some(column);
+            self.sort_descending = false;
+        }
+    }
+
+    /// Edits the filter while it is being typed.
+    fn handle_filter_key(&mut self, key_evt: crossterm::event::KeyEvent) {
+        match key_evt.code {
+            crossterm::event::KeyCode::Char(c) => self.filter.push(c),
+            crossterm::event::KeyCode::Backspace => {
+                self.filter.pop();
+            }
+            crossterm::event::KeyCode::Tab => self.fuzzy_filter = !self.fuzzy_filter,
+            crossterm::event::KeyCode::Enter => self.filter_input = false,
+            crossterm::event::KeyCode::Esc => {
+                self.filter.clear();
+                self.filter_input = false;
+            }
+            crossterm::event::KeyCode::Up => self.previous_row(),
+            crossterm::event::KeyCode::Down => self.next_row(),
+            _ => {}
+        }
+    }
+
+    /// Edits the pattern of the attachments to queue while it is being typed.
+    fn handle_select_key(&mut self, key_evt: crossterm::event::KeyEvent) {
+        let Some(input) = &mut self.select_input else {
+            return;
+        };
+        match key_evt.code {
+            crossterm::event::KeyCode::Char(c) => input.push(c),
+            crossterm::event::KeyCode::Backspace => {
+                input.pop();
+            }
+            crossterm::event::KeyCode::Enter => {
+                let pattern = self.select_input.take().unwrap_or_default();
+                self.select_matching(&pattern);
+            }
+            crossterm::event::KeyCode::Esc => self.select_input = None,
+            _ => {}
+        }
+    }
+
+    /// Returns whether an attachment matches the filter in its filename,
+    /// author or MIME type.
+    ///
+    /// Without fuzzy matching, each word of the filter has to be contained
+    /// in one of them, ignoring case.
+    fn matches_filter(&self, att: &Attachment, matcher: &SkimMatcherV2) -> bool {
+        let fields = [
+            Some(att.filename.as_str()),
+            att.author.as_deref(),
+            att.mime_type.as_deref(),
+        ];
+        let mut fields = fields.into_iter().flatten();
+        if self.fuzzy_filter {
+            fields.any(|field| matcher.fuzzy_match(field, &self.filter).is_some())
+        } else {
+            let fields: Vec<String> = fields.map(str::to_lowercase).collect();
+            self.filter
+                .to_lowercase()
+                .split_whitespace()
+                .all(|word| fields.iter().any(|field| field.contains(word)))
+        }
+    }
+
+    /// Returns the index of the attachment in the selected row.
+    fn selected_index(&self) -> Option<usize> {
+        self.table_state
+            .selected()
+            .and_then(|row| self.view.get(row).copied())
+    }
+
+    /// Sorts the rows, keeping the selected attachment selected.
+    fn update_view(&mut self) {
+        let selected = self.selected_index();
+
+        self.view = (0..self.attachments.len())
+            .filter(|&i| self.attachments[i].issue == self.current)
+            .collect();
+        if let Some(column) = self.sort {
+            let attachments = &self.attachments;
+            self.view.sort_by(|&a, &b| {
+                let (a, b) = (&attachments[a], &attachments[b]);
+                let order = match column {
+                    SortColumn::State => a.state.sort_rank().cmp(&b.state.sort_rank()),
+                    SortColumn::Filename => std::cmp::Ordering::Equal,
+                    SortColumn::Size => a.size.cmp(&b.size),
+                    // attachments without a valid timestamp come first
+                    SortColumn::Created => a.created_at.cmp(&b.created_at),
+                };
+                order.then_with(|| a.filename.to_lowercase().cmp(&b.filename.to_lowercase()))
+            });
+            if self.sort_descending {
+                self.view.reverse();
+            }
+        }
+
+        if !self.filter.is_empty() {
+            let matcher = SkimMatcherV2::default().ignore_case();
+            let view = std::mem::take(&mut self.view);
+            self.view = view
+                .into_iter()
+                .filter(|&i| self.matches_filter(&self.attachments[i], &matcher))
+                .collect();
+        }
+
+        if let Some(selected) = selected {
+            // select the first row if the selected attachment is hidden
+            let row = self.view.iter().position(|&i| i == selected);
+            self.table_state
+                .select(row.or((!self.view.is_empty()).then_some(0)));
+        } else if self.filter_input && !self.view.is_empty() {
+            self.table_state.select(Some(0));
+        }
+    }
+
+    /// Sorts by the next column, going back to the order of Jira after the
+    /// last one.
+    fn cycle_sort_column(&mut self) {
+        self.sort = match self.sort {
+            None => Some(SortColumn::Filename),
+            Some(SortColumn::Filename) => Some(SortColumn::Size),
+            Some(SortColumn::Size) => Some(SortColumn::Created),
+            Some(SortColumn::Created) => Some(SortColumn::State),
+            Some(SortColumn::State) => None,
+        };
+    }
+
+    /// Selects the row returned by `row` for the selected row and the number
+    /// of rows per page, limited to the last row.
+    fn select_row(&mut self, row: impl FnOnce(usize, usize) -> usize) {
+        if self.view.is_empty() {
+            return;
+        }
+        let selected = self.table_state.selected().unwrap_or(0);
+        let row = row(selected, self.page_rows).min(self.view.len() - 1);
+        self.table_state.select(Some(row));
+    }
+
+    fn next_row(&mut self) {
+        self.table_state.select(Some(
+            self.table_state
+                .selected()
+                .map(|i| std::cmp::min(i + 1, self.view.len().saturating_sub(1)))
+                .unwrap_or(0),
+        ));
+    }
+
+    fn previous_row(&mut self) {
+        self.table_state.select(Some(
+            self.table_state
+                .selected()
+                .map(|i| i.saturating_sub(1))
+                .unwrap_or(0),
+        ));
+    }
+
+    /// Steps the global bandwidth limit up or down, going from the highest
+    /// step to unlimited and back.
+    fn change_rate_limit(&mut self, increase: bool) {
+        let limiter = &self.download_settings.rate_limiter;
+        let mut steps = RATE_LIMIT_STEPS.iter().copied();
+        let rate = match (limiter.rate(), increase) {
+            (None, true) => None,
+            (None, false) => steps.next_back(),
+            (Some(rate), true) => steps.find(|&s| s > rate),
+            (Some(rate), false) => steps
+                .rfind(|&s| s < rate)
+                .or(RATE_LIMIT_STEPS.first().copied()),
+        };
+        info!("Changing rate limit to {:?} bytes/s", rate);
+        limiter.set_rate(rate);
+    }
+
+    fn toggle_selection(&mut self) {
+        if let Some(selected) = self.selected_index() {
+            let queued = self.attachments[selected].state == AttachmentState::Queued;
+            self.set_queued(selected, !queued);
+        }
+    }
+
+    /// Queues or unqueues an attachment, leaving attachments alone that are
+    /// being downloaded or have been downloaded.
+    fn set_queued(&mut self, index: usize, queued: bool) {
+        let att = &mut self.attachments[index];
+        att.state = match (&att.state, queued) {
+            (
+                AttachmentState::NotDownloaded
+                | AttachmentState::Paused { .. }
+                | AttachmentState::Available { .. }
+                | AttachmentState::Failed { errmsg: _ },
+                true,
+            ) => AttachmentState::Queued,
+            // an unqueued copy can still be copied
+            (AttachmentState::Queued, false) => match att.copy_from.take() {
+                Some((record, _)) => AttachmentState::Available { record },
+                None => AttachmentState::NotDownloaded,
+            },
+            _ => return,
+        };
+        self.queue_changed = true;
+    }
+
+    /// Queues or unqueues all visible attachments.
+    fn select_all(&mut self, queued: bool) {
+        for i in self.view.clone() {
+            self.set_queued(i, queued);
+        }
+    }
+
+    /// Unqueues the visible queued attachments and queues the others.
+    fn invert_selection(&mut self) {
+        for i in self.view.clone() {
+            let queued = self.attachments[i].state == AttachmentState::Queued;
+            self.set_queued(i, !queued);
+        }
+    }
+
+    /// Queues the selected attachment and the one above or below it, moving
+    /// the selection there.
+    fn extend_selection(&mut self, down: bool) {
+        if let Some(selected) = self.selected_index() {
+            self.set_queued(selected, true);
+        }
+        if down {
+            self.next_row();
+        } else {
+            self.previous_row();
+        }
+        if let Some(selected) = self.selected_index() {
+            self.set_queued(selected, true);
+        }
+    }
+
+    /// Queues the visible attachments whose filename matches the glob
+    /// `pattern`, ignoring case.
+    fn select_matching(&mut self, pattern: &str) {
+        if pattern.is_empty() {
+            return;
+        }
+        let matcher = match GlobBuilder::new(pattern).case_insensitive(true).build() {
+            Ok(glob) => glob.compile_matcher(),
+            Err(e) => {
+                self.notice = Some(format!("Invalid pattern {:?}: {}", pattern, e));
+                return;
+            }
+        };
+        let matching: Vec<usize> = self
+            .view
+            .iter()
+            .copied()
+            .filter(|&i| matcher.is_match(&self.attachments[i].filename))
+            .collect();
+        for &i in &matching {
+            self.set_queued(i, true);
+        }
+        self.notice = Some(format!(
+            "{} attachments match {:?}.",
+            matching.len(),
+            pattern
+        ));
+    }
+
+    /// Returns the number and total size of the queued attachments.
+    fn queued_summary(&self) -> (usize, u64) {
+        self.attachments
+            .iter()
+            .filter(|att| att.state == AttachmentState::Queued)
+            .fold((0, 0), |(count, size), att| (count + 1, size + att.size))
+    }
+
+    /// Pauses the download of the selected attachment or resumes it if it is
+    /// paused.
+    async fn toggle_pause(&mut self) {
+        let Some(selected) = self.selected_index() else {
+            return;
+        };
+        self.queue_changed = true;
+        match self.attachments[selected].state {
+            AttachmentState::Downloading { downloaded, total } => {
+                // Stop the download before it can be resumed, keeping the
+                // part file
+                if let Some(pos) = self
+                    .downloads
+                    .iter()
+                    .position(|ctrl| ctrl.attachment_index == selected)
+                {
+                    let ctrl = self.downloads.remove(pos);
+                    ctrl.task.abort();
+                    let _ = ctrl.task.await;
+                }
+                let att = &mut self.attachments[selected];
+                info!("Download paused for {}", att.filename);
+                att.state = AttachmentState::Paused { downloaded, total };
+                self.start_downloads();
+            }
+            AttachmentState::Paused { .. } => {
+                let att = &mut self.attachments[selected];
+                info!("Download resumed for {}", att.filename);
+                att.state = AttachmentState::Queued;
+                self.start_downloads();
+            }
+            _ => {}
+        }
+    }
+
+    /// Cancels the download of the selected attachment and removes the
+    /// partially downloaded file.
+    fn cancel_download(&mut self) {
+        let Some(selected) = self.selected_index() else {
+            return;
+        };
+        self.queue_changed = true;
+        let att = &mut self.attachments[selected];
+        let part_path = part_path(&self.issues[att.issue].folder.join(att.local_filename()));
+        match att.state {
+            AttachmentState::Downloading { .. } => {
+                info!("Download cancelled for {}", att.filename);
+                att.state = AttachmentState::NotDownloaded;
+                if let Some(pos) = self
+                    .downloads
+                    .iter()
+                    .position(|ctrl| ctrl.attachment_index == selected)
+                {
+                    let ctrl = self.downloads.remove(pos);
+                    // wait for the download to stop before removing its file
+                    tokio::spawn(async move {
+                        drop(ctrl.progress_rx);
+                        let _ = ctrl.task.await;
+                        let _ = tokio::fs::remove_file(part_path).await;
+                    });
+                }
+                self.start_downloads();
+            }
+            AttachmentState::Paused { .. } => {
+                info!("Download cancelled for {}", att.filename);
+                att.state = AttachmentState::NotDownloaded;
+                tokio::spawn(async move {
+                    let _ = tokio::fs::remove_file(part_path).await;
+                });
+            }
+            AttachmentState::Queued => {
+                att.state = match att.copy_from.take() {
+                    Some((record, _)) => AttachmentState::Available { record },
+                    None => AttachmentState::NotDownloaded,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Extracts the selected archive if it has been downloaded, otherwise
+    /// toggles whether it is extracted once its download has completed.
+    fn extract_selected(&mut self) {
+        let Some(selected) = self.selected_index() else {
+            return;
+        };
+        let att = &mut self.attachments[selected];
+        if ArchiveKind::from_filename(att.local_filename()).is_none() {
+            return;
+        }
+        match att.state {
+            AttachmentState::Downloaded | AttachmentState::ExtractionFailed { .. } => {
+                att.state = AttachmentState::Extracting {
+                    extracted: 0,
+                    total: att.size,
+                };
+                tokio::spawn(extract_attachment(
+                    selected,
+                    self.issues[att.issue].folder.join(att.local_filename()),
+                    self.task_tx.clone(),
+                ));
+            }
+            AttachmentState::Extracting { .. } | AttachmentState::Extracted => {}
+            _ => att.extract = !att.extract,
+        }
+    }
+
+    /// Returns the path an attachment is downloaded to.
+    fn file_path(&self, att: &Attachment) -> PathBuf {
+        self.issues[att.issue].folder.join(att.local_filename())
+    }
+
+    /// Returns the pending downloads of an earlier session of the current
+    /// issue, while the user has not decided whether to restore them.
+    fn saved_queue(&self) -> Option<&SavedQueue> {
+        self.issues[self.current].saved_queue.as_ref()
+    }
+
+    /// Returns the local file of an attachment, if it has been downloaded.
+    fn local_file(&self, att: &Attachment) -> Option<PathBuf> {
+        match &att.state {
+            AttachmentState::Downloaded
+            | AttachmentState::Extracting { .. }
+            | AttachmentState::Extracted
+            | AttachmentState::ExtractionFailed { .. } => Some(self.file_path(att)),
+            AttachmentState::Available { record } => Some(record.path.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the local file of the selected attachment, setting a notice if
+    /// it has not been downloaded.
+    fn selected_file(&mut self) -> Option<PathBuf> {
+        let att = &self.attachments[self.selected_index()?];
+        let path = self.local_file(att);
+        if path.is_none() {
+            self.notice = Some(format!(
+                "Attachment '{}' has not been downloaded yet.",
+                att.filename
+            ));
+        }
+        path
+    }
+
+    /// Opens the preview pane for the selected attachment, loading its
+    /// beginning from the downloaded file or else from Jira.
+    fn open_preview(&mut self) {
+        let Some(selected) = self.selected_index() else {
+            return;
+        };
+        let att = &self.attachments[selected];
+        let len = self.ui_settings.preview_size;
+        let local_file = self.local_file(att);
+        let jira = self.jira.clone();
+        let url = att.content.clone();
+        let task_tx = self.task_tx.clone();
+        tokio::spawn(async move {
+            let result = match local_file {
+                Some(path) => read_prefix(&path, len).await,
+                None => jira.fetch_prefix(&url, len).await,
+            };
+            let _ = task_tx.send(TaskEvent::Preview {
+                index: selected,
+                result: result.map_err(|e| e.to_string()),
+            });
+        });
+        self.preview = Some(Preview::new(selected, att.filename.clone()));
+    }
+
+    /// Opens the selected file with the opener configured for its extension,
+    /// or the default application.
+    fn open_selected(&mut self) {
+        let Some(path) = self.selected_file() else {
+            return;
+        };
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        let opener = self.ui_settings.openers.iter().find_map(|(ext, opener)| {
+            (Some(ext.trim_start_matches('.').to_lowercase()) == extension)
+                .then_some(opener.as_str())
+        });
+        if let Err(e) = open_detached(&path, opener) {
+            error!("{}", e);
+            self.notice = Some(e.to_string());
+        }
+    }
+
+    /// Opens the download folder with the default application.
+    fn open_folder(&mut self) {
+        if let Err(e) = open_detached(&self.issues[self.current].folder, None) {
+            error!("{}", e);
+            self.notice = Some(e.to_string());
+        }
+    }
+
+    /// Opens the selected file in a program taking over the terminal.
+    fn open_in_terminal(&mut self, command: fn(&Path) -> anyhow::Result<tokio::process::Command>) {
+        let Some(path) = self.selected_file() else {
+            return;
+        };
+        match command(&path) {
+            Ok(command) => self.pending_command = Some(command),
+            Err(e) => self.notice = Some(e.to_string()),
+        }
+    }
+
+    /// Returns whether an attachment is extracted once it has been
+    /// downloaded.
+    fn extract_after_download(&self, att: &Attachment) -> bool {
+        ArchiveKind::from_filename(att.local_filename()).is_some()
+            && (att.extract
+                || self
+                    .download_settings
+                    .extract
+                    .is_match(att.local_filename()))
+    }
+
+    fn update_status_message(&mut self) {
+        if let Some(saved_queue) = self.saved_queue() {
+            let keymap = &self.ui_settings.keymap;
+            let answers: Vec<String> = [Action::RestoreQueue, Action::DiscardQueue]
+                .into_iter()
+                .filter_map(|action| keymap.first_key(action))
+                .collect();
+            self.status_message = Some(format!(
+                "Restore {} pending downloads of the last session into {:?}? ({})",
+                saved_queue.entries.len(),
+                saved_queue.folder,
+                answers.join("/")
+            ));
+        } else if let Some(notice) = &self.notice {
+            self.status_message = Some(notice.clone());
+        } else if self.selected_index().is_none() && !self.downloads.is_empty() {
+            // Summarize all active transfers
+            let lines: Vec<String> = self
+                .downloads
+                .iter()
+                .map(|ctrl| {
+                    let att = &self.attachments[ctrl.attachment_index];
+                    // downloads of other issues continue in the background
+                    let name = if att.issue == self.current {
+                        att.filename.clone()
+                    } else {
+                        format!("{}/{}", self.issues[att.issue].key, att.filename)
+                    };
+                    match &att.state {
+                        AttachmentState::Downloading {
+                            downloaded,
+                            total: Some(total),
+                        } => format!(
+                            "{} {} ({} / {})",
+                            att.state,
+                            name,
+                            format_file_size(*downloaded),
+                            format_file_size(*total)
+                        ),
+                        AttachmentState::Downloading { downloaded, .. } => {
+                            format!("{} {} ({})", att.state, name, format_file_size(*downloaded))
+                        }
+                        state => format!("{} {}", state, name),
+                    }
+                })
+                .collect();
+            self.status_message = Some(lines.join("\n"));
+        } else if let Some(i) = self.selected_index() {
+            let att = &self.attachments[i];
+            self.status_message = match &att.state {
+                AttachmentState::NotDownloaded => {
+                    Some(format!("Attachment '{}' is not downloaded.", att.filename))
+                }
+                AttachmentState::Queued => Some(format!(
+                    "Attachment '{}' is queued for download.",
+                    att.filename
+                )),
+                AttachmentState::Downloading { downloaded, total } => {
+                    if let Some(total) = total {
+                        Some(format!(
+                            "Downloading '{}'... {}/{} bytes",
+                            att.filename, downloaded, total
+                        ))
+                    } else {
+                        Some(format!(
+                            "Downloading '{}'... {} bytes downloaded",
+                            att.filename, downloaded
+                        ))
+                    }
+                }
+                AttachmentState::Paused { downloaded, total } => Some(format!(
+                    "Download of '{}' paused at {} / {}.",
+                    att.filename,
+                    format_file_size(*downloaded),
+                    total.map_or("?".to_string(), format_file_size)
+                )),
+                AttachmentState::Downloaded => Some(format!(
+                    "Attachment '{}' has been downloaded.",
+                    att.filename
+                )),
+                AttachmentState::Available { record } => {
+                    let keymap = &self.ui_settings.keymap;
+                    let choices: Vec<String> = [
+                        (Action::CopyFromHistory, "to copy"),
+                        (Action::LinkFromHistory, "to link it here"),
+                        (Action::ToggleQueued, "to download it again"),
+                    ]
+                    .into_iter()
+                    .filter_map(|(action, what)| {
+                        keymap.first_key(action).map(|key| format!("{key} {what}"))
+                    })
+                    .collect();
+                    let mut message = format!(
+                        "Attachment '{}' has already been downloaded at {} on {}.",
+                        att.filename,
+                        record.path.display(),
+                        chrono::DateTime::parse_from_rfc3339(&record.timestamp)
+                            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_else(|_| record.timestamp.clone()),
+                    );
+                    if !choices.is_empty() {
+                        message.push_str(&format!("\nPress {}.", choices.join(", ")));
+                    }
+                    Some(message)
+                }
+                AttachmentState::Failed { errmsg } => Some(format!(
+                    "Attachment '{}' failed to download: {}",
+                    att.filename, errmsg
+                )),
+                AttachmentState::Extracting { extracted, total } => Some(format!(
+                    "Extracting '{}'... {}%",
+                    att.filename,
+                    extracted * 100 / (*total).max(1)
+                )),
+                AttachmentState::Extracted => Some(format!(
+                    "Attachment '{}' has been extracted to {}.",
+                    att.filename,
+                    extract_dir(&self.file_path(att)).display()
+                )),
+                AttachmentState::ExtractionFailed { errmsg } => Some(format!(
+                    "Attachment '{}' has been downloaded, but extracting it failed: {}",
+                    att.filename, errmsg
+                )),
+            };
+            if self.extract_after_download(att)
+                && !matches!(
+                    att.state,
+                    AttachmentState::Downloaded
+                        | AttachmentState::Extracting { .. }
+                        | AttachmentState::Extracted
+                        | AttachmentState::ExtractionFailed { .. }
+                )
+                && let Some(msg) = &mut self.status_message
+            {
+                msg.push_str("\nIt will be extracted after download.");
+            }
+            if let Some(msg) = &mut self.status_message {
+                for hook in &att.hook_outputs {
+                    msg.push_str(&format!(
+                        "\n{} `{}`: {}",
+                        if hook.success { "✓" } else { "✗" },
+                        hook.command,
+                        hook.status
+                    ));
+                    if let Some(line) = hook.output.lines().rfind(|l| !l.trim().is_empty()) {
+                        msg.push_str(&format!(" | {}", line.trim()));
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let mut toplayout =
+            ratatui::layout::Layout::vertical([Constraint::Fill(1), Constraint::Max(1)])
+                .split(frame.area())
+                .to_vec();
+        if self.issues.len() > 1 {
+            let [tabs_area, main_area] =
+                ratatui::layout::Layout::vertical([Constraint::Length(1), Constraint::Fill(1)])
+                    .areas(toplayout[0]);
+            self.render_tabs(frame, tabs_area);
+            toplayout[0] = main_area;
+        }
+
+        if self.show_browser
+            && let Some(browser) = &mut self.browser
+        {
+            browser.render(frame, toplayout[0], &self.ui_settings.theme);
+            self.render_help(frame, toplayout[1]);
+            return;
+        }
+
+        let table_height = self
+            .attachments
+            .iter()
+            .filter(|att| att.issue == self.current)
+            .count() as u16
+            + 5;
+        if self.preview.is_some() {
+            let layout = ratatui::layout::Layout::vertical([
+                Constraint::Max(table_height.min(toplayout[0].height * 2 / 5)),
+                Constraint::Fill(1),
+            ])
+            .spacing(ratatui::layout::Spacing::Overlap(1))
+            .split(toplayout[0]);
+
+            self.render_table(frame, layout[0]);
+            if let Some(preview) = &mut self.preview {
+                preview.render(frame, layout[1], &self.ui_settings.theme);
+            }
+            self.render_help(frame, toplayout[1]);
+            return;
+        }
+
+        // wide terminals show the details next to the table and status area,
+        // narrow ones instead of the status
+        let mut main_area = toplayout[0];
+        let side_details = self.show_details && main_area.width >= MIN_SIDE_DETAILS_WIDTH;
+        if side_details {
+            let [left, right] =
+                ratatui::layout::Layout::horizontal([Constraint::Fill(3), Constraint::Fill(2)])
+                    .spacing(ratatui::layout::Spacing::Overlap(1))
+                    .areas(main_area);
+            main_area = left;
+            self.render_details(frame, right);
+        }
+
+        // many attachments scroll instead of pushing the status area away
+        let layout = ratatui::layout::Layout::vertical([
+            Constraint::Max(table_height),
+            Constraint::Min(MIN_STATUS_HEIGHT),
+        ])
+        .spacing(ratatui::layout::Spacing::Overlap(1))
+        .split(main_area);
+
+        self.render_table(frame, layout[0]);
+        // the restore prompt is shown in the status area
+        if self.show_details && !side_details && self.saved_queue().is_none() {
+            self.render_details(frame, layout[1]);
+        } else {
+            self.render_status(frame, layout[1]);
+        }
+        self.render_help(frame, toplayout[1]);
+
+        if let Some(goto) = &self.goto {
+            goto.render(frame, toplayout[0], &self.ui_settings.theme);
+        }
+        if let Some(help) = &mut self.help {
+            help.render(frame, toplayout[0], &self.ui_settings.theme);
+        }
+    }
+
+    /// Renders a tab for each loaded issue, with the number of its active
+    /// downloads.
+    fn render_tabs(&self, frame: &mut Frame, area: Rect) {
+        let titles: Vec<String> = self
+            .issues
+            .iter()
+            .enumerate()
+            .map(|(i, issue)| {
+                let active = self
+                    .downloads
+                    .iter()
+                    .filter(|ctrl| self.attachments[ctrl.attachment_index].issue == i)
+                    .count();
+                if active > 0 {
+                    format!("{} {} ({}↓)", i + 1, issue.key, active)
+                } else {
+                    format!("{} {}", i + 1, issue.key)
+                }
+            })
+            .collect();
+        // only the tabs around the current one if they do not all fit
+        let widths: Vec<usize> = titles.iter().map(|title| title.width()).collect();
+        let visible = visible_tabs(&widths, self.current, area.width as usize);
+        let mut selected = self.current - visible.start;
+        let mut shown = Vec::new();
+        if visible.start > 0 {
+            shown.push("…".to_string());
+            selected += 1;
+        }
+        let hidden_after = visible.end < titles.len();
+        shown.extend(titles.into_iter().take(visible.end).skip(visible.start));
+        if hidden_after {
+            shown.push("…".to_string());
+        }
+        let tabs = ratatui::widgets::Tabs::new(shown)
+            .select(selected)
+            .highlight_style(self.ui_settings.theme.selection);
+        frame.render_widget(tabs, area);
+    }
+
+    /// Renders everything known about the selected attachment.
+    fn render_details(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .merge_borders(ratatui::symbols::merge::MergeStrategy::Exact)
+            .border_style(self.ui_settings.theme.border)
+            .title("Details");
+        let Some(att) = self.selected_index().map(|i| &self.attachments[i]) else {
+            let paragraph =
+                ratatui::widgets::Paragraph::new("No attachment selected.").block(block);
+            frame.render_widget(paragraph, area);
+            return;
+        };
+
+        let unknown = || "unknown".to_string();
+        let created = att.created_at.map_or_else(
+            || att.created.clone(),
+            |dt| dt.format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+        );
+        // partial downloads are kept in the .part file
+        let local_path = self.local_file(att).unwrap_or_else(|| self.file_path(att));
+        let on_disk = match &att.state {
+            AttachmentState::Downloading { downloaded, .. }
+            | AttachmentState::Paused { downloaded, .. } => {
+                Some((part_path(&local_path), *downloaded))
+            }
+            _ => att
+                .downloaded_file()
+                .map(|(path, size)| (path.to_path_buf(), size)),
+        };
+        let mut fields = vec![
+            ("Filename", att.filename.clone()),
+            (
+                "Size",
+                format!("{} bytes ({})", att.size, format_file_size(att.size)),
+            ),
+            ("Created", created),
+            ("Author", att.author.clone().unwrap_or_else(unknown)),
+            ("MIME type", att.mime_type.clone().unwrap_or_else(unknown)),
+            ("ID", att.id.clone()),
+            ("URL", att.content.clone()),
+            ("Local path", local_path.display().to_string()),
+            (
+                "On disk",
+                match on_disk {
+                    Some((path, size)) if path != local_path => {
+                        format!("{} bytes in {}", size, path.display())
+                    }
+                    Some((_, size)) => format!("{} bytes", size),
+                    None => "not downloaded".to_string(),
+                },
+            ),
+        ];
+        if let Some(compression) = att.compression {
+            fields.push(("Decompressed", format!("from {:?}", compression)));
+        }
+        match &att.state {
+            AttachmentState::Extracted => fields.push((
+                "Extracted to",
+                extract_dir(&local_path).display().to_string(),
+            )),
+            AttachmentState::Failed { errmsg } => fields.push(("Error", errmsg.clone())),
+            AttachmentState::ExtractionFailed { errmsg } => {
+                fields.push(("Extraction error", errmsg.clone()))
+            }
+            _ => {}
+        }
+
+        let label_style = Style::default().add_modifier(Modifier::BOLD);
+        let lines: Vec<ratatui::text::Line> = fields
+            .into_iter()
+            .map(|(label, value)| {
+                ratatui::text::Line::from(vec![
+                    ratatui::text::Span::styled(format!("{}: ", label), label_style),
+                    value.into(),
+                ])
+            })
+            .collect();
+        let paragraph = ratatui::widgets::Paragraph::new(lines)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .block(block);
+        frame.render_widget(paragraph, area);
+    }
+
+    /// Renders the rows of the table that fit into `area`, scrolled so the
+    /// selected row is visible.
+    fn render_table(&mut self, frame: &mut Frame, area: Rect) {
+        // the borders, the header and its margin take four lines
+        self.page_rows = (area.height as usize).saturating_sub(4).max(1);
+        let offset = self.scroll_offset();
+        *self.table_state.offset_mut() = offset;
+
+        let widths = self.column_widths(area.width.saturating_sub(2));
+        let filename_width = match widths[1] {
+            // keep a space between the filename and the size
+            Constraint::Length(width) => (width as usize).saturating_sub(1),
+            _ => unreachable!("the filename column has a fixed width"),
+        };
+        let rows = self
+            .view
+            .iter()
+            .skip(offset)
+            .take(self.page_rows)
+            .map(|&i| {
+                let att = &self.attachments[i];
+                ratatui::widgets::Row::new(vec![
+                    ratatui::text::Line::styled(
+                        att.state.to_string(),
+                        self.ui_settings.theme.state(&att.state),
+                    )
+                    .right_aligned(),
+                    truncate(&att.filename, filename_width).into(),
+                    format_file_size(att.size).into(),
+                    att.created.clone().into(),
+                ])
+            });
+
+        let t = ratatui::widgets::Table::new(rows, widths)
+            .header(
+                ratatui::widgets::Row::new(vec![
+                    ratatui::text::Line::from(self.header(SortColumn::State, "")).right_aligned(),
+                    self.header(SortColumn::Filename, "Filename").into(),
+                    self.header(SortColumn::Size, "Size").into(),
+                    self.header(SortColumn::Created, "Created").into(),
+                ])
+                .style(self.ui_settings.theme.header)
+                .bottom_margin(1),
+            )
+            .block(
+                ratatui::widgets::Block::default()
+                    .title(format!("{} Attachments", self.issues[self.current].key))
+                    .title_bottom(self.filter_title())
+                    .borders(ratatui::widgets::Borders::ALL)
+                    .border_style(self.ui_settings.theme.border)
+                    .merge_borders(ratatui::symbols::merge::MergeStrategy::Exact),
+            )
+            .row_highlight_style(self.ui_settings.theme.selection);
+
+        // only the visible rows have been built
+        let mut state = TableState::default()
+            .with_selected(self.table_state.selected().map(|row| row - offset));
+        frame.render_stateful_widget(t, area, &mut state);
+        self.table_area = area;
+
+        if self.view.len() > self.page_rows {
+            let mut scrollbar_state =
+                ratatui::widgets::ScrollbarState::new(self.view.len() - self.page_rows)
+                    .position(offset)
+                    .viewport_content_length(self.page_rows);
+            let scrollbar_area = Rect {
+                y: area.y + 3,
+                height: area.height.saturating_sub(4),
+                ..area
+            };
+            frame.render_stateful_widget(
+                ratatui::widgets::Scrollbar::new(
+                    ratatui::widgets::ScrollbarOrientation::VerticalRight,
+                )
+                .begin_symbol(None)
+                .end_symbol(None),
+                scrollbar_area,
+                &mut scrollbar_state,
+            );
+        }
+    }
+
+    /// Returns the first visible row, keeping the rows shown so far unless
+    /// the selected row is outside of them.
+    fn scroll_offset(&self) -> usize {
+        let mut offset = self
+            .table_state
+            .offset()
+            .min(self.view.len().saturating_sub(self.page_rows));
+        if let Some(selected) = self.table_state.selected() {
+            if selected < offset {
+                offset = selected;
+            } else if selected >= offset + self.page_rows {
+                offset = selected + 1 - self.page_rows;
+            }
+        }
+        offset
+    }
+
+    /// Returns the widths of the columns in a table that is `width` wide,
+    /// shortening the filename column if the filenames do not fit.
+    fn column_widths(&self, width: u16) -> [Constraint; 4] {
+        let lengths = self.issues[self.current].lengths;
+        let fixed = lengths.0 + lengths.2 + 1 + lengths.3 + 3;
+        let filename_width = (lengths.1 + 1)
+            .min((width as usize).saturating_sub(fixed))
+            .max(MIN_FILENAME_WIDTH);
+        [
+            Constraint::Length(lengths.0 as u16),
+            Constraint::Length(filename_width as u16),
+            Constraint::Length(lengths.2 as u16 + 1),
+            Constraint::Length(lengths.3 as u16),
+        ]
+    }
+
+    /// Returns the filter shown below the table.
+    fn filter_title(&self) -> String {
+        if !self.filter_input && self.filter.is_empty() {
+            return String::new();
+        }
+        format!(
+            "/{}{} ({}{} of {})",
+            self.filter,
+            if self.filter_input { "▏" } else { "" },
+            if self.fuzzy_filter { "fuzzy, " } else { "" },
+            self.view.len(),
+            self.attachments
+                .iter()
+                .filter(|att| att.issue == self.current)
+                .count()
+        )
+    }
+
+    /// Returns the header of a column, with an indicator if the table is
+    /// sorted by it.
+    fn header(&self, column: SortColumn, title: &str) -> String {
+        match self.sort {
+            Some(sort) if sort == column => {
+                let indicator = if self.sort_descending { "▼" } else { "▲" };
+                format!("{} {}", title, indicator).trim_start().to_string()
+            }
+            _ => title.to_string(),
+        }
+    }
+
+    fn render_status(&self, frame: &mut Frame, area: Rect) {
+        let (queued, queued_size) = self.queued_summary();
+        let title = if queued > 0 {
+            format!("{} queued, {}", queued, format_file_size(queued_size))
+        } else {
+            String::new()
+        };
+        let paragraph =
+            ratatui::widgets::Paragraph::new(self.status_message.clone().unwrap_or_default())
+                .block(
+                    Block::bordered()
+                        .merge_borders(ratatui::symbols::merge::MergeStrategy::Exact)
+                        .border_style(self.ui_settings.theme.border)
+                        .title_bottom(title),
+                );
+        frame.render_widget(paragraph, area);
+    }
+
+    fn render_help(&self, frame: &mut Frame, area: Rect) {
+        let rate_limit = self
+            .download_settings
+            .rate_limiter
+            .rate()
+            .map_or("unlimited".to_string(), |rate| {
+                format!("{}/s", format_file_size(rate))
+            });
+        if self.help.is_some() {
+            let text = self.hints(&[
+                (&[Action::HelpClose], "Close"),
+                (
+                    &[
+                        Action::HelpUp,
+                        Action::HelpDown,
+                        Action::HelpPageUp,
+                        Action::HelpPageDown,
+                        Action::HelpTop,
+                        Action::HelpBottom,
+                    ],
+                    "Scroll",
+                ),
+            ]);
+            let paragraph =
+                ratatui::widgets::Paragraph::new(text).style(self.ui_settings.theme.status_bar);
+            frame.render_widget(paragraph, area);
+            return;
+        }
+        if self.preview.as_ref().is_some_and(Preview::is_searching) {
+            let paragraph = ratatui::widgets::Paragraph::new("Enter: Search | Esc: Cancel")
+                .style(self.ui_settings.theme.status_bar);
+            frame.render_widget(paragraph, area);
+            return;
+        }
+        if self.preview.is_some() {
+            let text = self.hints(&[
+                (&[Action::PreviewClose], "Close"),
+                (
+                    &[
+                        Action::PreviewUp,
+                        Action::PreviewDown,
+                        Action::PreviewPageUp,
+                        Action::PreviewPageDown,
+                        Action::PreviewTop,
+                        Action::PreviewBottom,
+                    ],
+                    "Scroll",
+                ),
+                (&[Action::PreviewSearch], "Search"),
+                (
+                    &[Action::PreviewNextMatch, Action::PreviewPreviousMatch],
+                    "Next/Previous Match",
+                ),
+            ]);
+            let paragraph =
+                ratatui::widgets::Paragraph::new(text).style(self.ui_settings.theme.status_bar);
+            frame.render_widget(paragraph, area);
+            return;
+        }
+        if self.filter_input {
+            let paragraph = ratatui::widgets::Paragraph::new(
+                "Enter: Apply Filter | Esc: Clear Filter | Tab: Toggle Fuzzy Matching | ↑/↓: Navigate",
+            )
+            .style(self.ui_settings.theme.status_bar);
+            frame.render_widget(paragraph, area);
+            return;
+        }
+        if self.show_browser {
+            let text = if self.browser.as_ref().is_some_and(Browser::is_editing) {
+                "Enter: Search | Esc: Cancel".to_string()
+            } else {
+                self.hints(&[
+                    (&[Action::BrowserClose], "Back"),
+                    (&[Action::BrowserUp, Action::BrowserDown], "Navigate"),
+                    (&[Action::BrowserSwitchPane], "Filters/Issues"),
+                    (&[Action::BrowserOpen], "Run Filter/Open Issue"),
+                    (&[Action::BrowserEditQuery], "Edit JQL"),
+                ])
+            };
+            let paragraph =
+                ratatui::widgets::Paragraph::new(text).style(self.ui_settings.theme.status_bar);
+            frame.render_widget(paragraph, area);
+            return;
+        }
+        if self.goto.is_some() {
+            let paragraph = ratatui::widgets::Paragraph::new(
+                "Enter: Open Issue | Tab: Complete | ↑/↓: Choose | Esc: Cancel",
+            )
+            .style(self.ui_settings.theme.status_bar);
+            frame.render_widget(paragraph, area);
+            return;
+        }
+        if let Some(input) = &self.select_input {
+            let paragraph = ratatui::widgets::Paragraph::new(format!(
+                "Queue matching: {}▏ | Enter: Queue | Esc: Cancel",
+                input
+            ))
+            .style(self.ui_settings.theme.status_bar);
+            frame.render_widget(paragraph, area);
+            return;
+        }
+        if self.saved_queue().is_some() {
+            let text = self.hints(&[
+                (&[Action::RestoreQueue], "Restore"),
+                (&[Action::DiscardQueue], "Discard"),
+                (&[Action::Quit], "Quit"),
+            ]);
+            let paragraph =
+                ratatui::widgets::Paragraph::new(text).style(self.ui_settings.theme.status_bar);
+            frame.render_widget(paragraph, area);
+            return;
+        }
+
+        let rate_limit_label = format!("Rate Limit ({})", rate_limit);
+        let mut hints: Vec<(&[Action], &str)> = vec![
+            (&[Action::Help], "Help"),
+            (&[Action::Quit], "Quit"),
+            (&[Action::Up, Action::Down], "Navigate"),
+        ];
+        // only the actions that apply to the selected attachment
+        match self.selected_index().map(|i| &self.attachments[i]) {
+            Some(att) => {
+                match &att.state {
+                    AttachmentState::NotDownloaded
+                    | AttachmentState::Queued
+                    | AttachmentState::Failed { .. } => {
+                        hints.push((&[Action::ToggleQueued], "Queue/Unqueue"));
+                    }
+                    AttachmentState::Available { .. } => {
+                        hints.push((&[Action::ToggleQueued], "Queue"));
+                        hints.push((
+                            &[Action::CopyFromHistory, Action::LinkFromHistory],
+                            "Copy/Link",
+                        ));
+                    }
+                    AttachmentState::Downloading { .. } => {
+                        hints.push((&[Action::Pause], "Pause"));
+                        hints.push((&[Action::Cancel], "Cancel"));
+                    }
+                    AttachmentState::Paused { .. } => {
+                        hints.push((&[Action::Pause], "Resume"));
+                        hints.push((&[Action::Cancel], "Cancel"));
+                    }
+                    AttachmentState::Downloaded
+                    | AttachmentState::Extracted
+                    | AttachmentState::ExtractionFailed { .. } => {
+                        hints.push((&[Action::OpenFile], "Open"));
+                        if ArchiveKind::from_filename(att.local_filename()).is_some() {
+                            hints.push((&[Action::Extract], "Extract"));
+                        }
+                    }
+                    AttachmentState::Extracting { .. } => {}
+                }
+                hints.push((&[Action::Preview], "Preview"));
+                hints.push((&[Action::Details], "Details"));
+            }
+            None => hints.push((
+                &[
+                    Action::SelectAll,
+                    Action::SelectNone,
+                    Action::InvertSelection,
+                ],
+                "All/None/Invert",
+            )),
+        }
+        if self.queued_summary().0 > 0 {
+            hints.push((&[Action::StartDownloads], "Start Download"));
+        }
+        if self.filter.is_empty() {
+            hints.push((&[Action::Filter], "Filter"));
+        } else {
+            hints.push((&[Action::Back], "Clear Filter"));
+        }
+        if self.issues.len() > 1 {
+            hints.push((&[Action::PreviousIssue, Action::NextIssue], "Switch Issue"));
+        } else {
+            hints.push((&[Action::GotoIssue], "Go to Issue"));
+        }
+        if !self.downloads.is_empty() {
+            hints.push((
+                &[Action::RaiseRateLimit, Action::LowerRateLimit],
+                &rate_limit_label,
+            ));
+        }
+
+        let keymap = &self.ui_settings.keymap;
+        let mut entries: Vec<String> = hints
+            .into_iter()
+            .filter_map(|(actions, label)| keymap.hint(actions, label))
+            .collect();
+        if self.visual_anchor.is_some() {
+            entries.insert(0, "-- VISUAL --".to_string());
+        }
+        let status_text = entries.join(" | ");
+        let paragraph =
+            ratatui::widgets::Paragraph::new(status_text).style(self.ui_settings.theme.status_bar);
+        frame.render_widget(paragraph, area);
+    }
+
+    /// Returns the help bar text for the bound actions among `hints`.
+    fn hints(&self, hints: &[(&[Action], &str)]) -> String {
+        hints
+            .iter()
+            .filter_map(|(actions, label)| self.ui_settings.keymap.hint(actions, label))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    fn start_downloads(&mut self) {
+        while self.downloads.len() < self.download_settings.max_downloads {
+            // all issues share one queue, regardless of the filter and the
+            // issue shown
+            let Some(i) = self
+                .attachments
+                .iter()
+                .position(|a| a.state == AttachmentState::Queued)
+            else {
+                break;
+            };
+            if let Some((record, link)) = self.attachments[i].copy_from.take() {
+                self.start_copy(i, *record, link);
+                continue;
+            }
+            let extract = self.extract_after_download(&self.attachments[i]);
+            let a = &mut self.attachments[i];
+
+            let j = match self.download_settings.download_rate_limit {
+                Some(rate) => self
+                    .jira
+                    .clone()
+                    .with_rate_limiter(RateLimiter::new(Some(rate))),
+                None => self.jira.clone(),
+            };
+            let issue = &self.issues[a.issue];
+            let job = DownloadJob {
+                issue: issue.key.clone(),
+                attachment_id: a.id.clone(),
+                url: a.content.clone(),
+                file_path: issue.folder.join(a.local_filename()),
+                filename: a.filename.clone(),
+                size: a.size,
+                compression: a.compression,
+            };
+            let history = self.history.clone();
+            let settings = self.download_settings.clone();
+            let task_tx = self.task_tx.clone();
+            let (tx, rx) = watch::channel(jira::DownloadEvent::Starting);
+
+            debug!("Starting download for {}", a.filename);
+            self.queue_changed = true;
+            a.state = AttachmentState::Downloading {
+                downloaded: 0,
+                total: None,
+            };
+            a.hook_outputs.clear();
+
+            // spawn a tokio task to download
+            let task = tokio::spawn(async move {
+                let result = download_attachment(&j, &history, &settings, &job, tx.clone()).await;
+                // A closed channel means the download has been paused or
+                // cancelled
+                if result.is_err() && tx.is_closed() {
+                    return;
+                }
+                let hooks = (settings.hooks, j.base_url().to_string());
+                finish_download(i, result, &job, extract, Some(hooks), task_tx).await;
+            });
+
+            self.downloads.push(DownloadCtrl {
+                attachment_index: i,
+                progress_rx: rx,
+                task,
+            });
+        }
+    }
+
+    /// Queues the selected attachment to be copied or linked from where it
+    /// has been downloaded before into the download folder.
+    fn copy_from_history(&mut self, link: bool) {
+        let Some(selected) = self.selected_index() else {
+            return;
+        };
+        let att = &mut self.attachments[selected];
+        let AttachmentState::Available { record } = &att.state else {
+            return;
+        };
+        // copies wait for a free download slot like downloads
+        att.copy_from = Some((record.clone(), link));
+        att.state = AttachmentState::Queued;
+        self.queue_changed = true;
+        self.start_downloads();
+    }
+
+    /// Starts copying or linking an attachment from an earlier download of
+    /// it into the download folder.
+    fn start_copy(&mut self, index: usize, record: Record, link: bool) {
+        let att = &self.attachments[index];
+        let job = DownloadJob {
+            issue: self.issues[att.issue].key.clone(),
+            attachment_id: att.id.clone(),
+            url: att.content.clone(),
+            file_path: self.file_path(att),
+            filename: att.filename.clone(),
+            size: att.size,
+            compression: None,
+        };
+        let history = self.history.clone();
+        let extract = self.extract_after_download(att);
+        let task_tx = self.task_tx.clone();
+        let (tx, rx) = watch::channel(jira::DownloadEvent::Starting);
+
+        info!(
+            "{} {:?} to {:?}",
+            if link { "Linking" } else { "Copying" },
+            record.path,
+            job.file_path
+        );
+        self.attachments[index].state = AttachmentState::Downloading {
+            downloaded: 0,
+            total: Some(record.size),
+        };
+
+        let task = tokio::spawn(async move {
+            let result = copy_attachment(&history, &record, &job, link, tx.clone()).await;
+            // A closed channel means the copy has been paused or cancelled
+            if result.is_err() && tx.is_closed() {
+                return;
+            }
+            finish_download(index, result, &job, extract, None, task_tx).await;
+        });
+
+        self.downloads.push(DownloadCtrl {
+            attachment_index: index,
+            progress_rx: rx,
+            task,
+        });
+    }
+
+    fn update_download(&mut self, index: usize, evt: jira::DownloadEvent) {
+        let att = &mut self.attachments[index];
+        match evt {
+            jira::DownloadEvent::Starting => {}
+            jira::DownloadEvent::Progress { downloaded, total } => {
+                att.state = AttachmentState::Downloading { downloaded, total };
+            }
+            // the download task reports its result once the file is in place
+            _ => {}
+        }
+    }
+
+    async fn handle_task_event(&mut self, evt: TaskEvent) {
+        match evt {
+            TaskEvent::Downloaded { index, result } => {
+                let file_path = self.file_path(&self.attachments[index]);
+                let att = &mut self.attachments[index];
+                match result {
+                    Ok(size) => {
+                        info!("Download finished for {}", att.filename);
+                        att.state = AttachmentState::Downloaded;
+                        att.on_disk = Some((file_path, size));
+                    }
+                    Err(msg) => {
+                        error!("Download error for {}: {}", att.filename, msg);
+                        att.state = AttachmentState::Failed { errmsg: msg };
+                    }
+                }
+                self.queue_changed = true;
+                self.downloads.retain(|ctrl| ctrl.attachment_index != index);
+                self.start_downloads(); // start next download
+            }
+            TaskEvent::Extracting {
+                index,
+                extracted,
+                total,
+            } => {
+                self.attachments[index].state = AttachmentState::Extracting { extracted, total };
+            }
+            TaskEvent::Preview { index, result } => {
+                let att = &self.attachments[index];
+                let total = att.downloaded_file().map_or(att.size, |(_, size)| size);
+                if let Some(preview) = &mut self.preview
+                    && preview.attachment_index == index
+                {
+                    preview.set_content(result, total);
+                }
+            }
+            TaskEvent::HooksRun { index, outputs } => {
+                let att = &mut self.attachments[index];
+                // the attachment may have been downloaded again or removed
+                // while the hooks were running
+                if matches!(
+                    att.state,
+                    AttachmentState::Downloaded
+                        | AttachmentState::Extracting { .. }
+                        | AttachmentState::Extracted
+                        | AttachmentState::ExtractionFailed { .. }
+                ) {
+                    att.hook_outputs = outputs;
+                }
+            }
+            TaskEvent::Extracted { index, result } => {
+                let att = &mut self.attachments[index];
+                att.state = match result {
+                    Ok(()) => {
+                        info!("Extracted {}", att.filename);
+                        AttachmentState::Extracted
+                    }
+                    Err(errmsg) => {
+                        error!("Failed to extract {}: {}", att.filename, errmsg);
+                        AttachmentState::ExtractionFailed { errmsg }
+                    }
+                };
+            }
+            TaskEvent::IssueLoaded { key, result } => match result {
+                Ok((attachments, saved_queue)) => {
+                    self.add_loaded_issue(key, attachments, saved_queue).await;
+                }
+                Err(e) => {
+                    error!("Failed to load issue {}: {}", key, e);
+                    self.notice = Some(format!("Failed to load issue {}: {}", key, e));
+                }
+            },
+            TaskEvent::Projects(projects) => {
+                if let Some(goto) = &mut self.goto {
+                    goto.set_projects(projects.clone());
+                }
+                self.projects = projects;
+            }
+            TaskEvent::Filters(result) => {
+                if let Some(browser) = &mut self.browser {
+                    browser.set_filters(result);
+                }
+            }
+            TaskEvent::SearchResults { jql, result } => {
+                if let Some(browser) = &mut self.browser {
+                    browser.set_results(&jql, result);
+                }
+            }
+        }
+    }
+
+    /// Shows the issue browser, loading the favourite filters and the
+    /// recently viewed issues when it is opened for the first time.
+    fn open_browser(&mut self) {
+        self.show_browser = true;
+        if self.browser.is_some() {
+            return;
+        }
+        self.browser = Some(Browser::new());
+
+        let jira = self.jira.clone();
+        let task_tx = self.task_tx.clone();
+        tokio::spawn(async move {
+            let result = jira.fetch_favourite_filters().await;
+            let _ = task_tx.send(TaskEvent::Filters(result.map_err(|e| e.to_string())));
+        });
+        self.search_issues(DEFAULT_JQL.to_string());
+    }
+
+    /// Searches for the issues matching `jql` in the background.
+    fn search_issues(&self, jql: String) {
+        let jira = self.jira.clone();
+        let task_tx = self.task_tx.clone();
+        tokio::spawn(async move {
+            let result = jira.search_issues(&jql, MAX_SEARCH_RESULTS).await;
+            let _ = task_tx.send(TaskEvent::SearchResults {
+                jql,
+                result: result.map_err(|e| e.to_string()),
+            });
+        });
+    }
+
+    /// Loads the projects of the Jira instance in the background, to
+    /// complete issue keys.
+    fn load_projects(&self) {
+        let jira = self.jira.clone();
+        let task_tx = self.task_tx.clone();
+        tokio::spawn(async move {
+            match jira.fetch_projects().await {
+                Ok(projects) => {
+                    let _ = task_tx.send(TaskEvent::Projects(projects));
+                }
+                Err(e) => debug!("Failed to fetch projects: {}", e),
+            }
+        });
+    }
+
+    /// Opens the prompt for the issue to show.
+    fn open_goto_prompt(&mut self) {
+        let recent = self
+            .history
+            .recent_issues(self.jira.base_url(), RECENT_ISSUES)
+            .unwrap_or_else(|e| {
+                error!("Failed to look up recent issues: {}", e);
+                Vec::new()
+            });
+        self.goto = Some(GotoPrompt::new(recent, self.projects.clone()));
+    }
+
+    /// Adds an issue the user has opened to the recent issues offered by the
+    /// go to prompt.
+    fn record_recent_issue(&self, key: &str) {
+        if let Err(e) = self.history.add_recent_issue(self.jira.base_url(), key) {
+            error!("Failed to record recent issue {}: {}", key, e);
+        }
+    }
+
+    /// Shows the attachments of an issue, loading them in the background if
+    /// the issue has not been loaded yet.
+    fn open_issue(&mut self, key: String) {
+        if let Err(e) = check_issue_key(&key) {
+            self.notice = Some(e.to_string());
+            return;
+        }
+        if let Some(index) = self.issues.iter().position(|issue| issue.key == key) {
+            self.record_recent_issue(&key);
+            self.switch_issue(index);
+            return;
+        }
+
+        info!("Loading issue {}", key);
+        self.notice = Some(format!("Loading issue {}...", key));
+        let jira = self.jira.clone();
+        let data_dir = self.data_dir.clone();
+        let task_tx = self.task_tx.clone();
+        tokio::spawn(async move {
+            let result = async {
+                let attachments = jira.fetch_attachments(&key).await?;
+                let saved_queue = SavedQueue::load(&data_dir, jira.base_url(), &key).await?;
+                anyhow::Ok((attachments, saved_queue))
+            }
+            .await;
+            let _ = task_tx.send(TaskEvent::IssueLoaded {
+                key,
+                result: result.map_err(|e| e.to_string()),
+            });
+        });
+    }
+
+    /// Adds a loaded issue and shows its attachments, restoring its saved
+    /// queue like the one of the first issue.
+    async fn add_loaded_issue(
+        &mut self,
+        key: String,
+        attachments: Vec<jira::Attachment>,
+        saved_queue: Option<SavedQueue>,
+    ) {
+        if self.issues.iter().any(|issue| issue.key == key) {
+            // opened twice while loading
+            return;
+        }
+        let folder = match std::path::absolute(&key) {
+            Ok(folder) => folder,
+            Err(e) => {
+                self.notice = Some(format!("Invalid issue key {}: {}", key, e));
+                return;
+            }
+        };
+        if let Err(e) = tokio::fs::create_dir_all(&folder).await {
+            self.notice = Some(format!(
+                "Failed to create download directory {:?}: {}",
+                folder, e
+            ));
+            return;
+        }
+
+        self.record_recent_issue(&key);
+        let index = self.add_issue(key, folder, attachments);
+        self.init_states(index).await;
+        if let Some(saved_queue) = saved_queue {
+            if self.confirm_restore {
+                self.issues[index].saved_queue = Some(saved_queue);
+            } else {
+                self.apply_saved_queue(index, saved_queue).await;
+            }
+        }
+        self.notice = None;
+        self.switch_issue(index);
+    }
+
+    /// Shows the attachments of another loaded issue, keeping the downloads
+    /// of the current one running.
+    fn switch_issue(&mut self, index: usize) {
+        self.issues[self.current].selected = self.selected_index();
+        self.current = index;
+        self.table_state = TableState::default();
+        self.preview = None;
+        self.last_click = None;
+        self.visual_anchor = None;
+        self.update_view();
+        let row = self.issues[index]
+            .selected
+            .and_then(|selected| self.view.iter().position(|&i| i == selected));
+        self.table_state.select(row);
+    }
+}
+
+/// Downloads an attachment, records it in the download history and adds it
+/// to the content store.
+///
+/// The partially downloaded file is removed on failure, but kept when the
+/// download has been paused or cancelled by closing the channel.
+pub(crate) async fn download_attachment(
+    jira: &jira::Jira,
+    history: &History,
+    settings: &DownloadSettings,
+    job: &DownloadJob,
+    tx: watch::Sender<jira::DownloadEvent>,
+) -> anyhow::Result<()> {
+    let part_path = part_path(&job.file_path);
+    let result = match job.compression {
+        // decompressed downloads start over when resumed
+        Some(compression) => {
+            decompress_to_file(
+                jira,
+                &job.url,
+                compression,
+                job.size,
+                &job.file_path,
+                tx.clone(),
+            )
+            .await
+        }
+        None => resume_to_file(jira, &job.url, &job.file_path, &part_path, tx.clone()).await,
+    };
+    if let Err(e) = result {
+        // A closed channel means the download has been paused or
+        // cancelled, which takes care of the part file itself
+        if !tx.is_closed() {
+            let _ = tokio::fs::remove_file(&part_path).await;
         }
-    }
-}
-
-async fn download_attachment(
-    jira: &crate::jira::Jira,
-    url: String,
-    file_path: PathBuf,
-    tx: tokio::sync::watch::Sender<jira::DownloadEvent>,
-) -> anyhow::Result<()> {
-    let (tmp_file, tmp_file_path) = create_tmp_download_file(&file_path).await?;
-    let tmp_path_to_remove = tmp_file_path.clone();
-
-    if let Err(e) = jira
-        .download_attachment(url, tmp_file, tx)
-        .and_then(|()| async move {
-            tokio::fs::rename(&tmp_file_path, file_path)
-                .await
-                .map_err(Into::into)
-        })
-        .await
-    {
-        let _ = tokio::fs::remove_file(&tmp_path_to_remove).await;
         return Err(e);
     }
 
+    match history
+        .record_download(
+            jira.base_url(),
+            &job.issue,
+            &job.attachment_id,
+            &job.file_path,
+        )
+        .await
+    {
+        Ok(record) => {
+            if let Some(store) = &settings.content_store
+                && let Err(e) = store.add(&job.file_path, &record.sha256).await
+            {
+                error!(
+                    "Failed to add {:?} to the content store: {}",
+                    job.file_path, e
+                );
+            }
+        }
+        Err(e) => error!("Failed to record download of {:?}: {}", job.file_path, e),
+    }
+
+    Ok(())
+}
+
+/// Reports the result of a download or copy, then runs the given hooks with
+/// the base URL of the Jira instance and extracts the downloaded archive if
+/// requested.
+///
+/// The hooks run in a task of their own once the download has been reported,
+/// so they neither hold its download slot nor delay the extraction.
+async fn finish_download(
+    index: usize,
+    result: anyhow::Result<()>,
+    job: &DownloadJob,
+    extract: bool,
+    hooks: Option<(Vec<Hook>, String)>,
+    task_tx: mpsc::UnboundedSender<TaskEvent>,
+) {
+    let downloaded = result.is_ok();
+    let result = match result {
+        Ok(()) => Ok(tokio::fs::metadata(&job.file_path)
+            .await
+            .map_or(job.size, |metadata| metadata.len())),
+        Err(e) => Err(e.to_string()),
+    };
+    let _ = task_tx.send(TaskEvent::Downloaded { index, result });
+    if downloaded
+        && let Some((hooks, base_url)) = hooks
+        && !hooks.is_empty()
+    {
+        let job = job.clone();
+        let task_tx = task_tx.clone();
+        tokio::spawn(async move {
+            let outputs = run_hooks(&hooks, &base_url, &job).await;
+            let _ = task_tx.send(TaskEvent::HooksRun { index, outputs });
+        });
+    }
+    if downloaded && extract {
+        extract_attachment(index, job.file_path.clone(), task_tx).await;
+    }
+}
+
+/// Extracts a downloaded archive into the folder next to it, reporting the
+/// progress as task events.
+async fn extract_attachment(
+    index: usize,
+    file_path: PathBuf,
+    task_tx: mpsc::UnboundedSender<TaskEvent>,
+) {
+    let progress_tx = task_tx.clone();
+    let mut last_percent = None;
+    let result = extract_archive(file_path, move |extracted, total| {
+        // only report changes of whole percents
+        let percent = extracted * 100 / total.max(1);
+        if last_percent != Some(percent) {
+            last_percent = Some(percent);
+            let _ = progress_tx.send(TaskEvent::Extracting {
+                index,
+                extracted,
+                total,
+            });
+        }
+    })
+    .await;
+    let _ = task_tx.send(TaskEvent::Extracted {
+        index,
+        result: result.map(|_| ()).map_err(|e| e.to_string()),
+    });
+}
+
+/// Copies or hard links a previously downloaded attachment to the path of
+/// `job`, falling back to a symbolic link if a hard link is not possible.
+async fn copy_attachment(
+    history: &History,
+    record: &Record,
+    job: &DownloadJob,
+    link: bool,
+    tx: watch::Sender<jira::DownloadEvent>,
+) -> anyhow::Result<()> {
+    let part_path = part_path(&job.file_path);
+    let _ = tokio::fs::remove_file(&part_path).await;
+
+    let copy = async {
+        if link {
+            match tokio::fs::hard_link(&record.path, &part_path).await {
+                Ok(()) => Ok(()),
+                Err(e) => {
+                    debug!("Hard link failed ({}), creating a symbolic link", e);
+                    symlink(&record.path, &part_path).await
+                }
+            }
+        } else {
+            copy_file(&record.path, &part_path, &tx).await
+        }
+    };
+    let result = tokio::select! {
+        result = copy => result,
+        _ = tx.closed() => Err(std::io::Error::other("copy cancelled")),
+    };
+    // A closed channel means the copy has been paused or cancelled, which
+    // takes care of the part file itself
+    if tx.is_closed() {
+        return Err(anyhow::anyhow!("Copy of {:?} cancelled", record.path));
+    }
+    if let Err(e) = result.and(tokio::fs::rename(&part_path, &job.file_path).await) {
+        let _ = tokio::fs::remove_file(&part_path).await;
+        return Err(anyhow::anyhow!("Failed to copy {:?}: {}", record.path, e));
+    }
+
+    history.insert(&Record {
+        issue: job.issue.clone(),
+        path: std::path::absolute(&job.file_path)?,
+        timestamp: chrono::Local::now().to_rfc3339(),
+        ..record.clone()
+    })?;
+    Ok(())
+}
+
+/// Copies a file, reporting the progress through `tx`.
+async fn copy_file(
+    from: &Path,
+    to: &Path,
+    tx: &watch::Sender<jira::DownloadEvent>,
+) -> std::io::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut src = tokio::fs::File::open(from).await?;
+    let total = src.metadata().await?.len();
+    let mut dst = tokio::fs::File::create(to).await?;
+    let mut buf = vec![0; 1 << 20];
+    let mut copied = 0;
+    loop {
+        let n = src.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        dst.write_all(&buf[..n]).await?;
+        copied += n as u64;
+        let _ = tx.send(jira::DownloadEvent::Progress {
+            downloaded: copied,
+            total: Some(total),
+        });
+    }
+    dst.flush().await
+}
+
+#[cfg(unix)]
+async fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    tokio::fs::symlink(original, link).await
+}
+
+#[cfg(windows)]
+async fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    tokio::fs::symlink_file(original, link).await
+}
+
+/// Reads the first `len` bytes of a file.
+async fn read_prefix(path: &Path, len: u64) -> anyhow::Result<Vec<u8>> {
+    use tokio::io::AsyncReadExt;
+
+    let mut data = Vec::new();
+    tokio::fs::File::open(path)
+        .await?
+        .take(len)
+        .read_to_end(&mut data)
+        .await?;
+    Ok(data)
+}
+
+/// Checks that `key` looks like an issue key such as `PROJ-123`, as it names
+/// the download folder and the saved queue of the issue.
+///
+/// # Errors
+///
+/// Returns an error if the key is empty or contains other characters than
+/// ASCII letters, digits, `_` and `-`, such as `/` or `..`.
+pub fn check_issue_key(key: &str) -> anyhow::Result<()> {
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(anyhow::anyhow!("Invalid issue key {:?}", key));
+    }
     Ok(())
 }
 
-impl From<crate::jira::Attachment> for Attachment {
-    fn from(att: crate::jira::Attachment) -> Self {
+/// Returns the range of tabs to show around the `current` one, so that they
+/// fit into `width` columns together with a `…` tab on each side hiding more.
+fn visible_tabs(widths: &[usize], current: usize, width: usize) -> Range<usize> {
+    // each tab is padded by a space on both sides and followed by a divider,
+    // except for the last one
+    let fits = |range: Range<usize>| {
+        let markers = usize::from(range.start > 0) + usize::from(range.end < widths.len());
+        widths[range].iter().map(|w| w + 3).sum::<usize>() + markers * 4 <= width + 1
+    };
+    let mut visible = current..current + 1;
+    loop {
+        let mut grown = false;
+        if visible.end < widths.len() && fits(visible.start..visible.end + 1) {
+            visible.end += 1;
+            grown = true;
+        }
+        if visible.start > 0 && fits(visible.start - 1..visible.end) {
+            visible.start -= 1;
+            grown = true;
+        }
+        if !grown {
+            return visible;
+        }
+    }
+}
+
+/// Shortens `text` to at most `width` columns, ending it with an ellipsis
+/// if it is too long.
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    let mut truncated_width = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if truncated_width + char_width + 1 > width {
+            break;
+        }
+        truncated.push(c);
+        truncated_width += char_width;
+    }
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+impl From<jira::Attachment> for Attachment {
+    fn from(att: jira::Attachment) -> Self {
+        let created_at =
+            chrono::DateTime::parse_from_str(&att.created, "%Y-%m-%dT%H:%M:%S%.3f%z").ok();
         Self {
+            issue: 0,
+            id: att.id,
             filename: att.filename,
-            size: att.size as usize,
-            created: chrono::DateTime::parse_from_str(&att.created, "%Y-%m-%dT%H:%M:%S%.3f%z")
+            size: att.size,
+            created: created_at
                 .map(|dt| {
                     dt.with_timezone(&chrono::Local)
                         .format("%Y-%m-%d %H:%M")
                         .to_string()
                 })
-                .unwrap_or_else(|_| att.created.clone()),
+                .unwrap_or_else(|| att.created.clone()),
+            created_at,
+            author: att.author,
+            mime_type: att.mime_type,
             content: att.content,
             state: AttachmentState::NotDownloaded,
+            extract: false,
+            compression: None,
+            hook_outputs: Vec::new(),
+            on_disk: None,
+            copy_from: None,
+        }
+    }
+}
+
+impl Attachment {
+    /// Returns the name of the downloaded file, which lacks the compression
+    /// extension if the attachment is decompressed while downloading.
+    fn local_filename(&self) -> &str {
+        self.compression.map_or(&self.filename, |compression| {
+            compression.strip_extension(&self.filename)
+        })
+    }
+
+    /// Returns the downloaded file and its size, if the attachment has been
+    /// downloaded here or to another folder before.
+    fn downloaded_file(&self) -> Option<(&Path, u64)> {
+        match &self.state {
+            AttachmentState::Downloaded
+            | AttachmentState::Extracting { .. }
+            | AttachmentState::Extracted
+            | AttachmentState::ExtractionFailed { .. } => self
+                .on_disk
+                .as_ref()
+                .map(|(path, size)| (path.as_path(), *size)),
+            AttachmentState::Available { record } => Some((&record.path, record.size)),
+            _ => None,
+        }
+    }
+}
+
+impl AttachmentState {
+    /// Returns the position of the state when sorting by state, active
+    /// downloads first.
+    fn sort_rank(&self) -> u8 {
+        match self {
+            AttachmentState::Downloading { .. } => 0,
+            AttachmentState::Extracting { .. } => 1,
+            AttachmentState::Queued => 2,
+            AttachmentState::Paused { .. } => 3,
+            AttachmentState::Failed { .. } => 4,
+            AttachmentState::ExtractionFailed { .. } => 5,
+            AttachmentState::NotDownloaded => 6,
+            AttachmentState::Available { .. } => 7,
+            AttachmentState::Downloaded => 8,
+            AttachmentState::Extracted => 9,
         }
     }
 }
@@ -481,31 +3172,60 @@ impl std::fmt::Display for AttachmentState {
             AttachmentState::Queued => write!(f, ">"),
             AttachmentState::Downloading { downloaded, total } => {
                 if let Some(total) = total {
-                    let percent = *downloaded * 100 / *total;
+                    let percent = *downloaded * 100 / (*total).max(1);
                     write!(f, "{}%", percent)
                 } else {
                     write!(f, "↓")
                 }
             }
+            AttachmentState::Paused { .. } => write!(f, "‖"),
             AttachmentState::Downloaded => write!(f, "✓"),
+            AttachmentState::Available { .. } => write!(f, "≡"),
             AttachmentState::Failed { errmsg: _ } => write!(f, "/!\\"),
+            AttachmentState::Extracting { extracted, total } => {
+                write!(f, "⇲{}", *extracted * 100 / (*total).max(1))
+            }
+            AttachmentState::Extracted => write!(f, "⊞"),
+            AttachmentState::ExtractionFailed { .. } => write!(f, "✓!"),
         }
     }
 }
 
-pub fn format_file_size(size: usize) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB", "PB"];
-    let mut size = size as f64;
-    let mut unit_idx = 0;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_issue_key_rejects_paths() {
+        assert!(check_issue_key("PROJ-123").is_ok());
+        assert!(check_issue_key("MY_PROJ2-1").is_ok());
+        assert!(check_issue_key("").is_err());
+        assert!(check_issue_key("..").is_err());
+        assert!(check_issue_key("../PROJ-1").is_err());
+        assert!(check_issue_key("PROJ/1").is_err());
+        assert!(check_issue_key(r"PROJ\1").is_err());
+        assert!(check_issue_key("/tmp").is_err());
+    }
+
+    #[test]
+    fn visible_tabs_shows_all_tabs_that_fit() {
+        // " 1 A │ 2 B │ 3 C " is 17 columns wide
+        assert_eq!(visible_tabs(&[3, 3, 3], 0, 17), 0..3);
+        assert_eq!(visible_tabs(&[3, 3, 3], 2, 40), 0..3);
+    }
 
-    while size >= 1024.0 && unit_idx < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_idx += 1;
+    #[test]
+    fn visible_tabs_scrolls_to_the_current_tab() {
+        let widths = [3; 30];
+        // three tabs and one `…` fit, a fourth tab does not
+        assert_eq!(visible_tabs(&widths, 0, 21), 0..3);
+        // two tabs between two `…`
+        assert_eq!(visible_tabs(&widths, 14, 21), 14..16);
+        assert_eq!(visible_tabs(&widths, 29, 21), 27..30);
     }
 
-    if unit_idx == 0 {
-        format!("{} {}", size as u64, UNITS[unit_idx])
-    } else {
-        format!("{:.2} {}", size, UNITS[unit_idx])
+    #[test]
+    fn visible_tabs_keeps_the_current_tab_if_nothing_fits() {
+        assert_eq!(visible_tabs(&[3, 30, 3], 1, 10), 1..2);
     }
 }
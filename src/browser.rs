@@ -0,0 +1,271 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use jira_downloader::{Filter, IssueSummary};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, List, ListState, Row, Table, TableState},
+};
+
+use crate::{keymap::Action, theme::Theme};
+
+/// The query the browser starts with, listing the recently viewed issues.
+pub const DEFAULT_JQL: &str = "issue in issueHistory() ORDER BY lastViewed DESC";
+
+/// A screen for finding issues through the favourite filters of the user or
+/// a JQL query.
+#[derive(Debug)]
+pub struct Browser {
+    filters: Vec<Filter>,
+    filter_state: ListState,
+    issues: Vec<IssueSummary>,
+    issue_state: TableState,
+    /// The query the issues have been searched with.
+    jql: String,
+    /// The query being typed after `/`.
+    jql_input: Option<String>,
+    focus: Focus,
+    /// Why there are no filters or issues, e.g. while they are loading.
+    filters_message: Option<String>,
+    issues_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Filters,
+    Issues,
+}
+
+/// What to do after a key press in the browser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrowserAction {
+    /// Nothing besides updating the browser.
+    None,
+    /// Return to the attachments.
+    Close,
+    /// Search for the issues matching a JQL query.
+    Search(String),
+    /// Show the attachments of the issue with the given key.
+    Open(String),
+}
+
+impl Browser {
+    /// Creates a browser whose filters and issues are still loading.
+    pub fn new() -> Self {
+        Self {
+            filters: Vec::new(),
+            filter_state: ListState::default(),
+            issues: Vec::new(),
+            issue_state: TableState::default(),
+            jql: DEFAULT_JQL.to_string(),
+            jql_input: None,
+            focus: Focus::Issues,
+            filters_message: Some("Loading...".to_string()),
+            issues_message: Some("Searching...".to_string()),
+        }
+    }
+
+    /// Sets the loaded favourite filters.
+    pub fn set_filters(&mut self, result: Result<Vec<Filter>, String>) {
+        match result {
+            Ok(filters) => {
+                self.filters_message = filters
+                    .is_empty()
+                    .then(|| "No favourite filters.".to_string());
+                self.filters = filters;
+                self.filter_state
+                    .select((!self.filters.is_empty()).then_some(0));
+            }
+            Err(e) => self.filters_message = Some(e),
+        }
+    }
+
+    /// Sets the issues found with `jql`, unless another query has been
+    /// searched since.
+    pub fn set_results(&mut self, jql: &str, result: Result<Vec<IssueSummary>, String>) {
+        if jql != self.jql {
+            return;
+        }
+        match result {
+            Ok(issues) => {
+                self.issues_message = issues.is_empty().then(|| "No issues found.".to_string());
+                self.issues = issues;
+            }
+            Err(e) => {
+                self.issues_message = Some(e);
+                self.issues.clear();
+            }
+        }
+        self.issue_state
+            .select((!self.issues.is_empty()).then_some(0));
+    }
+
+    /// Starts searching for the issues matching `jql`.
+    fn search(&mut self, jql: String) -> BrowserAction {
+        self.jql = jql.clone();
+        self.issues.clear();
+        self.issue_state.select(None);
+        self.issues_message = Some("Searching...".to_string());
+        self.focus = Focus::Issues;
+        BrowserAction::Search(jql)
+    }
+
+    /// Returns whether the JQL query is being edited.
+    pub fn is_editing(&self) -> bool {
+        self.jql_input.is_some()
+    }
+
+    /// Handles a key press while the JQL query is being edited.
+    pub fn handle_input_key(&mut self, key_evt: KeyEvent) -> BrowserAction {
+        if let Some(input) = &mut self.jql_input {
+            match key_evt.code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let jql = self.jql_input.take().unwrap_or_default();
+                    if !jql.trim().is_empty() {
+                        return self.search(jql.trim().to_string());
+                    }
+                }
+                KeyCode::Esc => self.jql_input = None,
+                _ => {}
+            }
+        }
+        BrowserAction::None
+    }
+
+    /// Performs an action of the browser.
+    pub fn perform(&mut self, action: Action) -> BrowserAction {
+        match action {
+            Action::BrowserClose => return BrowserAction::Close,
+            Action::BrowserSwitchPane => {
+                self.focus = match self.focus {
+                    Focus::Filters => Focus::Issues,
+                    Focus::Issues => Focus::Filters,
+                };
+            }
+            Action::BrowserUp => match self.focus {
+                Focus::Filters => self.filter_state.select_previous(),
+                Focus::Issues => self.issue_state.select_previous(),
+            },
+            Action::BrowserDown => match self.focus {
+                Focus::Filters if !self.filters.is_empty() => self.filter_state.select_next(),
+                Focus::Issues if !self.issues.is_empty() => self.issue_state.select_next(),
+                _ => {}
+            },
+            Action::BrowserEditQuery => self.jql_input = Some(self.jql.clone()),
+            Action::BrowserOpen => match self.focus {
+                Focus::Filters => {
+                    if let Some(filter) = self.selected_filter() {
+                        let jql = filter.jql.clone();
+                        return self.search(jql);
+                    }
+                }
+                Focus::Issues => {
+                    if let Some(issue) = self.selected_issue() {
+                        return BrowserAction::Open(issue.key.clone());
+                    }
+                }
+            },
+            _ => {}
+        }
+        BrowserAction::None
+    }
+
+    fn selected_filter(&self) -> Option<&Filter> {
+        self.filter_state.selected().and_then(|i| {
+            self.filters
+                .get(i.min(self.filters.len().saturating_sub(1)))
+        })
+    }
+
+    fn selected_issue(&self) -> Option<&IssueSummary> {
+        self.issue_state
+            .selected()
+            .and_then(|i| self.issues.get(i.min(self.issues.len().saturating_sub(1))))
+    }
+
+    /// Renders the filters next to the found issues into `area`.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let [filters_area, issues_area] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Fill(1)])
+                .spacing(ratatui::layout::Spacing::Overlap(1))
+                .areas(area);
+
+        let focused = |focus| {
+            if self.focus == focus && self.jql_input.is_none() {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            }
+        };
+
+        let filters_block = Block::bordered()
+            .merge_borders(ratatui::symbols::merge::MergeStrategy::Exact)
+            .border_style(theme.border)
+            .title(Line::styled("Favourite Filters", focused(Focus::Filters)));
+        let items: Vec<String> = match &self.filters_message {
+            Some(message) => vec![message.clone()],
+            None => self.filters.iter().map(|f| f.name.clone()).collect(),
+        };
+        let filters = List::new(items)
+            .block(filters_block)
+            .highlight_style(theme.selection);
+        frame.render_stateful_widget(filters, filters_area, &mut self.filter_state);
+
+        let bottom = match &self.jql_input {
+            Some(input) => format!("JQL: {}▏", input),
+            None => format!("JQL: {}", self.jql),
+        };
+        let issues_block = Block::bordered()
+            .merge_borders(ratatui::symbols::merge::MergeStrategy::Exact)
+            .border_style(theme.border)
+            .title(Line::styled("Issues", focused(Focus::Issues)))
+            .title_bottom(bottom);
+        let rows: Vec<Row> = match &self.issues_message {
+            Some(message) => vec![Row::new(vec![String::new(), message.clone()])],
+            None => self
+                .issues
+                .iter()
+                .map(|issue| {
+                    Row::new(vec![
+                        issue.key.clone(),
+                        issue.status.clone(),
+                        issue.attachments.to_string(),
+                        issue.summary.clone(),
+                    ])
+                })
+                .collect(),
+        };
+        let key_width = self
+            .issues
+            .iter()
+            .map(|issue| issue.key.len())
+            .max()
+            .unwrap_or(0)
+            .max(3) as u16;
+        let status_width = self
+            .issues
+            .iter()
+            .map(|issue| issue.status.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(6) as u16;
+        let issues = Table::new(
+            rows,
+            [
+                Constraint::Length(key_width),
+                Constraint::Length(status_width),
+                Constraint::Length(5),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(vec!["Key", "Status", "Files", "Summary"]).style(theme.header))
+        .block(issues_block)
+        .row_highlight_style(theme.selection);
+        frame.render_stateful_widget(issues, issues_area, &mut self.issue_state);
+    }
+}
@@ -0,0 +1,79 @@
+//! Decompression of single compressed files while downloading them.
+
+use async_compression::tokio::write::{BzDecoder, GzipDecoder, XzDecoder, ZstdDecoder};
+use tokio::io::AsyncWrite;
+
+/// A compression format of a single compressed file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// gzip, `.gz`
+    Gzip,
+    /// xz, `.xz`
+    Xz,
+    /// bzip2, `.bz2`
+    Bzip2,
+    /// Zstandard, `.zst`
+    Zstd,
+}
+
+const EXTENSIONS: &[(&str, Compression)] = &[
+    (".gz", Compression::Gzip),
+    (".xz", Compression::Xz),
+    (".bz2", Compression::Bzip2),
+    (".zst", Compression::Zstd),
+];
+
+impl Compression {
+    /// Determines the compression format from the extension of `filename`.
+    ///
+    /// Compressed tarballs like `logs.tar.gz` are archives of several files
+    /// rather than a single compressed file and yield `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use jira_downloader::Compression;
+    ///
+    /// assert_eq!(Compression::from_filename("server.log.gz"), Some(Compression::Gzip));
+    /// assert_eq!(Compression::from_filename("logs.tar.gz"), None);
+    /// ```
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let lower = filename.to_ascii_lowercase();
+        EXTENSIONS
+            .iter()
+            .find(|(ext, _)| lower.ends_with(ext))
+            .filter(|(ext, _)| {
+                let stem = &lower[..lower.len() - ext.len()];
+                !stem.is_empty() && !stem.ends_with(".tar")
+            })
+            .map(|&(_, compression)| compression)
+    }
+
+    /// Returns `filename` without the extension of the compression format,
+    /// e.g. `server.log` for `server.log.gz`.
+    pub fn strip_extension(self, filename: &str) -> &str {
+        let (ext, _) = EXTENSIONS
+            .iter()
+            .find(|(_, compression)| *compression == self)
+            .expect("every compression format has an extension");
+        if filename.to_ascii_lowercase().ends_with(ext) {
+            &filename[..filename.len() - ext.len()]
+        } else {
+            filename
+        }
+    }
+
+    /// Wraps `writer` so the data written to it is decompressed.
+    pub(crate) fn decoder<'a, W: AsyncWrite + Unpin + Send + 'a>(
+        self,
+        writer: W,
+    ) -> Box<dyn AsyncWrite + Unpin + Send + 'a> {
+        match self {
+            Compression::Gzip => Box::new(GzipDecoder::new(writer)),
+            Compression::Xz => Box::new(XzDecoder::new(writer)),
+            Compression::Bzip2 => Box::new(BzDecoder::new(writer)),
+            Compression::Zstd => Box::new(ZstdDecoder::new(writer)),
+        }
+    }
+}
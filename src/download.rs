@@ -0,0 +1,184 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use futures::TryFutureExt;
+use tokio::sync::watch::Sender;
+
+use crate::{
+    compression::Compression,
+    jira::{DownloadEvent, Jira},
+};
+
+/// Downloads an attachment to `file_path`.
+///
+/// The content is first written to a new `.part` file next to `file_path`,
+/// which is renamed to `file_path` once the download has completed. An
+/// existing file at `file_path` is replaced. On failure the `.part` file is
+/// removed again.
+///
+/// # Arguments
+///
+/// * `jira` - The client to download with
+/// * `url` - The content URL of the attachment
+/// * `file_path` - The path to store the attachment at
+/// * `tx` - A channel sender for download progress events
+///
+/// # Errors
+///
+/// Returns an error if the `.part` file cannot be created, the download fails
+/// or the downloaded file cannot be renamed.
+pub async fn download_to_file(
+    jira: &Jira,
+    url: &str,
+    file_path: &Path,
+    tx: Sender<DownloadEvent>,
+) -> Result<()> {
+    let (tmp_file, tmp_file_path) = create_tmp_download_file(file_path).await?;
+    let tmp_path_to_remove = tmp_file_path.clone();
+
+    if let Err(e) = jira
+        .download_attachment(url.to_string(), tmp_file, tx)
+        .and_then(|()| async move {
+            tokio::fs::rename(&tmp_file_path, file_path)
+                .await
+                .map_err(Into::into)
+        })
+        .await
+    {
+        let _ = tokio::fs::remove_file(&tmp_path_to_remove).await;
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Downloads a compressed attachment to `file_path`, decompressing it on the
+/// fly.
+///
+/// The content is written to the `.part` file of `file_path` (see
+/// [`part_path`]) first, which is renamed once the download has completed.
+/// The `.part` file is removed on failure, but kept when the download is
+/// paused or cancelled by closing `tx`, like by [`resume_to_file`]. As the
+/// decompression cannot continue in the middle of the stream, an existing
+/// `.part` file is overwritten from the beginning. `file_path` is usually the
+/// attachment's filename without the compression extension, see
+/// [`Compression::strip_extension`].
+///
+/// # Arguments
+///
+/// * `jira` - The client to download with
+/// * `url` - The content URL of the attachment
+/// * `compression` - The compression format of the attachment
+/// * `size` - The compressed size of the attachment as reported by Jira
+/// * `file_path` - The path to store the decompressed attachment at
+/// * `tx` - A channel sender for download progress events
+///
+/// # Errors
+///
+/// Returns an error if the `.part` file cannot be created, the download or
+/// decompression fails or the downloaded file cannot be renamed.
+pub async fn decompress_to_file(
+    jira: &Jira,
+    url: &str,
+    compression: Compression,
+    size: u64,
+    file_path: &Path,
+    tx: Sender<DownloadEvent>,
+) -> Result<()> {
+    let part_path = part_path(file_path);
+    let part_file = tokio::fs::File::create(&part_path)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create file {:?}: {}", part_path, e))?;
+
+    if let Err(e) = jira
+        .download_decompressed(url.to_string(), part_file, compression, size, tx.clone())
+        .and_then(|()| async {
+            tokio::fs::rename(&part_path, file_path)
+                .await
+                .map_err(Into::into)
+        })
+        .await
+    {
+        // a closed channel means the download has been paused or cancelled,
+        // which takes care of the part file itself
+        if !tx.is_closed() {
+            let _ = tokio::fs::remove_file(&part_path).await;
+        }
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Downloads an attachment to `file_path`, continuing a previous partial
+/// download stored in `part_path`.
+///
+/// The content is appended to `part_path`, which is created if it does not
+/// exist yet, and renamed to `file_path` once the download has completed.
+/// Unlike [`download_to_file`], the `.part` file is kept on failure or when
+/// the download is cancelled, so it can be resumed by calling this function
+/// again.
+///
+/// # Arguments
+///
+/// * `jira` - The client to download with
+/// * `url` - The content URL of the attachment
+/// * `file_path` - The path to store the attachment at
+/// * `part_path` - The path of the partially downloaded file
+/// * `tx` - A channel sender for download progress events
+///
+/// # Errors
+///
+/// Returns an error if the `.part` file cannot be opened, the download fails
+/// or the downloaded file cannot be renamed.
+pub async fn resume_to_file(
+    jira: &Jira,
+    url: &str,
+    file_path: &Path,
+    part_path: &Path,
+    tx: Sender<DownloadEvent>,
+) -> Result<()> {
+    let part_file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(part_path)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to open file {:?}: {}", part_path, e))?;
+    let offset = part_file.metadata().await?.len();
+
+    jira.resume_attachment(url.to_string(), part_file, offset, tx)
+        .await?;
+    tokio::fs::rename(part_path, file_path).await?;
+
+    Ok(())
+}
+
+/// Returns the path of the partially downloaded file of `file_path`, which
+/// is `file_path` with a `.part` extension added.
+pub fn part_path(file_path: &Path) -> PathBuf {
+    let mut part_path = file_path.to_path_buf();
+    part_path.add_extension("part");
+    part_path
+}
+
+async fn create_tmp_download_file(file_path: &Path) -> Result<(tokio::fs::File, PathBuf)> {
+    let mut tmp_file_path = file_path.to_path_buf();
+    loop {
+        tmp_file_path.add_extension("part");
+        match tokio::fs::File::create_new(&tmp_file_path).await {
+            Ok(file) => break Ok((file, tmp_file_path)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                // try again with a new name
+                continue;
+            }
+            Err(e) => {
+                break Err(anyhow::anyhow!(
+                    "Failed to create file {:?}: {}",
+                    tmp_file_path,
+                    e
+                ));
+            }
+        }
+    }
+}
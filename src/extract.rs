@@ -0,0 +1,403 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow, bail};
+use tracing::debug;
+
+use jira_downloader::internal::part_path;
+
+/// The archive formats that can be extracted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    /// A `.zip` archive.
+    Zip,
+    /// A gzip compressed tarball, `.tar.gz` or `.tgz`.
+    TarGz,
+}
+
+const EXTENSIONS: &[(&str, ArchiveKind)] = &[
+    (".zip", ArchiveKind::Zip),
+    (".tar.gz", ArchiveKind::TarGz),
+    (".tgz", ArchiveKind::TarGz),
+];
+
+impl ArchiveKind {
+    /// Determines the archive format from the extension of `filename`.
+    ///
+    /// Returns `None` if the file is not an archive that can be extracted.
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let filename = filename.to_ascii_lowercase();
+        EXTENSIONS
+            .iter()
+            .find(|(ext, _)| filename.ends_with(ext))
+            .map(|&(_, kind)| kind)
+    }
+}
+
+/// Returns the folder an archive is extracted to, which is next to the
+/// archive and named like it without the archive extension.
+pub fn extract_dir(archive: &Path) -> PathBuf {
+    let name = archive
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let lower = name.to_ascii_lowercase();
+    let stem = EXTENSIONS
+        .iter()
+        .find(|(ext, _)| lower.ends_with(ext))
+        .map_or(name.as_str(), |(ext, _)| &name[..name.len() - ext.len()]);
+    let dir_name = if stem.is_empty() {
+        format!("{}.d", name)
+    } else {
+        stem.to_string()
+    };
+    archive.with_file_name(dir_name)
+}
+
+/// Extracts `archive` into its [`extract_dir`] on a blocking thread.
+///
+/// `progress` is called with the number of processed and total bytes.
+pub async fn extract_archive(
+    archive: PathBuf,
+    progress: impl FnMut(u64, u64) + Send + 'static,
+) -> Result<PathBuf> {
+    tokio::task::spawn_blocking(move || {
+        let dest = extract_dir(&archive);
+        extract(&archive, &dest, progress).map(|()| dest)
+    })
+    .await?
+}
+
+/// Extracts an archive into the folder `dest`, calling `progress` with the
+/// number of processed and total bytes.
+///
+/// The entries are extracted into a temporary folder first, which is renamed
+/// to `dest` once the whole archive has been extracted. Archives containing
+/// absolute paths, paths leaving the target folder or links are rejected.
+///
+/// # Errors
+///
+/// Returns an error if the archive format is not supported, `dest` exists
+/// already, the archive is invalid or contains a rejected entry.
+pub fn extract(archive: &Path, dest: &Path, progress: impl FnMut(u64, u64)) -> Result<()> {
+    let kind = archive
+        .file_name()
+        .and_then(|name| ArchiveKind::from_filename(&name.to_string_lossy()))
+        .ok_or_else(|| anyhow!("{:?} is not a supported archive", archive))?;
+    if dest.exists() {
+        bail!("{:?} exists already", dest);
+    }
+
+    let tmp_dir = part_path(dest);
+    if tmp_dir.exists() {
+        // left over from an interrupted extraction
+        std::fs::remove_dir_all(&tmp_dir)?;
+    }
+    std::fs::create_dir_all(&tmp_dir)?;
+
+    debug!("Extracting {:?} to {:?}", archive, dest);
+    let result = match kind {
+        ArchiveKind::Zip => extract_zip(archive, &tmp_dir, progress),
+        ArchiveKind::TarGz => extract_tar_gz(archive, &tmp_dir, progress),
+    }
+    .and_then(|()| std::fs::rename(&tmp_dir, dest).map_err(Into::into));
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&tmp_dir);
+    }
+    result
+}
+
+fn extract_zip(archive: &Path, dest: &Path, mut progress: impl FnMut(u64, u64)) -> Result<()> {
+    let mut zip = zip::ZipArchive::new(BufReader::new(File::open(archive)?))?;
+    let total = (0..zip.len())
+        .map(|i| zip.by_index_raw(i).map(|file| file.size()))
+        .sum::<zip::result::ZipResult<u64>>()?;
+
+    let mut extracted = 0;
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let path = entry_path(Path::new(file.name()))?;
+        if file.is_symlink() {
+            bail!("Refusing to extract symbolic link {:?}", file.name());
+        }
+
+        let target = dest.join(path);
+        if file.is_dir() {
+            std::fs::create_dir_all(&target)?;
+        } else {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::io::copy(&mut file, &mut File::create(&target)?)?;
+        }
+        extracted += file.size();
+        progress(extracted, total);
+    }
+    Ok(())
+}
+
+fn extract_tar_gz(archive: &Path, dest: &Path, progress: impl FnMut(u64, u64)) -> Result<()> {
+    let file = File::open(archive)?;
+    let total = file.metadata()?.len();
+    // the uncompressed size is unknown, so report the compressed bytes read
+    let reader = ProgressReader {
+        inner: BufReader::new(file),
+        read: 0,
+        total,
+        progress,
+    };
+
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(reader));
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.into_owned();
+        let path = entry_path(&name)?;
+        let target = dest.join(path);
+
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_symlink() || entry_type.is_hard_link() {
+            bail!("Refusing to extract link {:?}", name);
+        } else if entry_type.is_dir() {
+            std::fs::create_dir_all(&target)?;
+        } else if entry_type.is_file() {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::io::copy(&mut entry, &mut File::create(&target)?)?;
+        } else {
+            debug!("Skipping {:?} of type {:?}", name, entry_type);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the path of an archive entry relative to the target folder,
+/// rejecting absolute paths and paths leaving the target folder.
+fn entry_path(name: &Path) -> Result<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                bail!(
+                    "Refusing to extract {:?} outside of the target folder",
+                    name
+                )
+            }
+        }
+    }
+    Ok(path)
+}
+
+/// Reports the number of bytes read from the wrapped reader.
+struct ProgressReader<R, F> {
+    inner: R,
+    read: u64,
+    total: u64,
+    progress: F,
+}
+
+impl<R: Read, F: FnMut(u64, u64)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        (self.progress)(self.read, self.total);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    /// A folder in the system's temporary folder, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "jira-downloader-test-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    enum Entry<'a> {
+        File(&'a str, &'a [u8]),
+        Symlink(&'a str, &'a str),
+        HardLink(&'a str, &'a str),
+    }
+
+    fn write_zip(path: &Path, entries: &[Entry]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for entry in entries {
+            match entry {
+                Entry::File(name, data) => {
+                    zip.start_file(*name, options).unwrap();
+                    zip.write_all(data).unwrap();
+                }
+                Entry::Symlink(name, target) => zip.add_symlink(*name, *target, options).unwrap(),
+                Entry::HardLink(..) => unreachable!("zip archives have no hard links"),
+            }
+        }
+        zip.finish().unwrap();
+    }
+
+    fn write_tar_gz(path: &Path, entries: &[Entry]) {
+        let gz = flate2::write::GzEncoder::new(
+            File::create(path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut tar = tar::Builder::new(gz);
+        for entry in entries {
+            let (name, data, entry_type, link) = match *entry {
+                Entry::File(name, data) => (name, data, tar::EntryType::Regular, None),
+                Entry::Symlink(name, target) => {
+                    (name, &[][..], tar::EntryType::Symlink, Some(target))
+                }
+                Entry::HardLink(name, target) => {
+                    (name, &[][..], tar::EntryType::Link, Some(target))
+                }
+            };
+            let mut header = tar::Header::new_old();
+            // written directly, as `set_path` refuses unsafe paths
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_entry_type(entry_type);
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            if let Some(link) = link {
+                header.set_link_name(link).unwrap();
+            }
+            header.set_cksum();
+            tar.append(&header, data).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap();
+    }
+
+    /// Extracts an archive with `entries`, checking that nothing is left
+    /// behind if the extraction fails.
+    fn extract_entries(name: &str, entries: &[Entry]) -> Result<TempDir> {
+        let dir = TempDir::new(name);
+        let archive = dir.0.join(name);
+        match ArchiveKind::from_filename(name) {
+            Some(ArchiveKind::Zip) => write_zip(&archive, entries),
+            Some(ArchiveKind::TarGz) => write_tar_gz(&archive, entries),
+            None => unreachable!(),
+        }
+        let dest = extract_dir(&archive);
+        let result = extract(&archive, &dest, |_, _| {});
+        if result.is_err() {
+            assert!(!dest.exists());
+            assert!(!part_path(&dest).exists());
+        }
+        result.map(|()| dir)
+    }
+
+    fn assert_refused(name: &str, entries: &[Entry]) {
+        let err = extract_entries(name, entries).err().unwrap();
+        assert!(err.to_string().starts_with("Refusing"), "{}", err);
+    }
+
+    #[test]
+    fn entry_path_keeps_relative_paths() {
+        assert_eq!(
+            entry_path(Path::new("a/./b/c.txt")).unwrap(),
+            Path::new("a/b/c.txt")
+        );
+        assert_eq!(
+            entry_path(Path::new("./c.txt")).unwrap(),
+            Path::new("c.txt")
+        );
+    }
+
+    #[test]
+    fn entry_path_rejects_parent_dirs() {
+        assert!(entry_path(Path::new("../evil.txt")).is_err());
+        assert!(entry_path(Path::new("a/../../evil.txt")).is_err());
+        assert!(entry_path(Path::new("a/..")).is_err());
+    }
+
+    #[test]
+    fn entry_path_rejects_absolute_paths() {
+        assert!(entry_path(Path::new("/etc/passwd")).is_err());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn entry_path_rejects_prefixes() {
+        assert!(entry_path(Path::new(r"C:\evil.txt")).is_err());
+        assert!(entry_path(Path::new(r"C:evil.txt")).is_err());
+        assert!(entry_path(Path::new(r"\\server\share\evil.txt")).is_err());
+    }
+
+    #[test]
+    fn extracts_zip() {
+        let dir = extract_entries(
+            "logs.zip",
+            &[Entry::File("a.txt", b"a"), Entry::File("sub/b.txt", b"b")],
+        )
+        .unwrap();
+        assert_eq!(std::fs::read(dir.0.join("logs/a.txt")).unwrap(), b"a");
+        assert_eq!(std::fs::read(dir.0.join("logs/sub/b.txt")).unwrap(), b"b");
+    }
+
+    #[test]
+    fn extracts_tar_gz() {
+        let dir = extract_entries(
+            "logs.tar.gz",
+            &[Entry::File("a.txt", b"a"), Entry::File("sub/b.txt", b"b")],
+        )
+        .unwrap();
+        assert_eq!(std::fs::read(dir.0.join("logs/a.txt")).unwrap(), b"a");
+        assert_eq!(std::fs::read(dir.0.join("logs/sub/b.txt")).unwrap(), b"b");
+    }
+
+    #[test]
+    fn rejects_zip_leaving_target_folder() {
+        assert_refused("parent.zip", &[Entry::File("../evil.txt", b"x")]);
+        assert_refused("absolute.zip", &[Entry::File("/evil.txt", b"x")]);
+    }
+
+    #[test]
+    fn rejects_zip_symlinks() {
+        let entries = [
+            Entry::File("a.txt", b"a"),
+            Entry::Symlink("link", "/etc/passwd"),
+        ];
+        assert_refused("symlink.zip", &entries);
+    }
+
+    #[test]
+    fn rejects_tar_gz_leaving_target_folder() {
+        assert_refused("parent.tgz", &[Entry::File("../evil.txt", b"x")]);
+        assert_refused("absolute.tgz", &[Entry::File("/evil.txt", b"x")]);
+    }
+
+    #[test]
+    fn rejects_tar_gz_links() {
+        let entries = [
+            Entry::File("a.txt", b"a"),
+            Entry::Symlink("link", "/etc/passwd"),
+        ];
+        assert_refused("symlink.tgz", &entries);
+        let entries = [Entry::File("a.txt", b"a"), Entry::HardLink("link", "a.txt")];
+        assert_refused("hardlink.tgz", &entries);
+    }
+}
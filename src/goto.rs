@@ -0,0 +1,165 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use jira_downloader::Project;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+};
+
+use crate::theme::Theme;
+
+/// The most candidates shown below the input.
+const MAX_CANDIDATES: usize = 10;
+
+/// A prompt for the key of an issue to open, offering the recently opened
+/// issues and completing project keys.
+#[derive(Debug)]
+pub struct GotoPrompt {
+    input: String,
+    recent: Vec<String>,
+    projects: Vec<Project>,
+    /// The highlighted candidate, if any.
+    selected: Option<usize>,
+}
+
+/// What to do after a key press in the prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GotoAction {
+    /// Keep the prompt open.
+    Continue,
+    /// Close the prompt.
+    Close,
+    /// Close the prompt and open the issue with the given key.
+    Open(String),
+}
+
+#[derive(Debug, Clone)]
+enum Candidate<'a> {
+    Issue(&'a str),
+    Project(&'a Project),
+}
+
+impl GotoPrompt {
+    /// Creates an empty prompt offering the `recent` issues, most recent
+    /// first, and completing the keys of `projects`.
+    pub fn new(recent: Vec<String>, projects: Vec<Project>) -> Self {
+        Self {
+            input: String::new(),
+            recent,
+            projects,
+            selected: None,
+        }
+    }
+
+    /// Sets the projects whose keys are completed, e.g. once they have been
+    /// loaded.
+    pub fn set_projects(&mut self, projects: Vec<Project>) {
+        self.projects = projects;
+        self.selected = None;
+    }
+
+    /// Returns the recent issues containing the input and, until the input
+    /// contains a `-`, the projects whose key starts with it.
+    fn candidates(&self) -> Vec<Candidate<'_>> {
+        let input = self.input.trim().to_uppercase();
+        let issues = self
+            .recent
+            .iter()
+            .filter(|issue| issue.to_uppercase().contains(&input))
+            .map(|issue| Candidate::Issue(issue));
+        let projects = self
+            .projects
+            .iter()
+            .filter(|project| !input.contains('-') && project.key.starts_with(&input))
+            .map(Candidate::Project);
+        issues.chain(projects).take(MAX_CANDIDATES).collect()
+    }
+
+    /// Handles a key press.
+    pub fn handle_key(&mut self, key_evt: KeyEvent) -> GotoAction {
+        let count = self.candidates().len();
+        match key_evt.code {
+            KeyCode::Esc => return GotoAction::Close,
+            KeyCode::Char(c) => {
+                self.input.push(c.to_ascii_uppercase());
+                self.selected = None;
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                self.selected = None;
+            }
+            KeyCode::Down if count > 0 => {
+                self.selected = Some(self.selected.map_or(0, |i| (i + 1).min(count - 1)));
+            }
+            KeyCode::Up => self.selected = self.selected.and_then(|i| i.checked_sub(1)),
+            KeyCode::Tab => self.complete(self.selected.unwrap_or(0)),
+            KeyCode::Enter => match self.selected {
+                Some(i) => {
+                    if let Some(Candidate::Issue(issue)) = self.candidates().get(i) {
+                        return GotoAction::Open(issue.to_string());
+                    }
+                    self.complete(i);
+                }
+                None if self.input.trim().contains('-') => {
+                    return GotoAction::Open(self.input.trim().to_string());
+                }
+                None => self.complete(0),
+            },
+            _ => {}
+        }
+        GotoAction::Continue
+    }
+
+    /// Replaces the input with the candidate at `index`, or the project key
+    /// followed by `-`.
+    fn complete(&mut self, index: usize) {
+        let completion = match self.candidates().get(index) {
+            Some(Candidate::Issue(issue)) => issue.to_string(),
+            Some(Candidate::Project(project)) => format!("{}-", project.key),
+            None => return,
+        };
+        self.input = completion;
+        self.selected = None;
+    }
+
+    /// Renders the prompt as a popup at the top of `area`.
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let candidates = self.candidates();
+        let width = area.width.min(60);
+        let height = area.height.min(candidates.len() as u16 + 3);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + 1,
+            width,
+            height,
+        }
+        .intersection(area);
+
+        let mut lines = vec![Line::from(format!("> {}▏", self.input))];
+        lines.extend(candidates.iter().enumerate().map(|(i, candidate)| {
+            let style = if self.selected == Some(i) {
+                theme.selection
+            } else {
+                Style::default()
+            };
+            match candidate {
+                Candidate::Issue(issue) => Line::styled(format!("  {}", issue), style),
+                Candidate::Project(project) => Line::from(vec![
+                    Span::styled(format!("  {}-", project.key), style),
+                    Span::styled(
+                        format!("  {}", project.name),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ),
+                ]),
+            }
+        }));
+
+        let block = Block::bordered()
+            .border_style(theme.border)
+            .title("Go to issue");
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+}
@@ -0,0 +1,138 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+};
+
+use crate::{
+    keymap::{Action, Category, Keymap},
+    theme::Theme,
+};
+
+/// A popup listing every action with its keys, grouped by category.
+#[derive(Debug)]
+pub struct HelpOverlay {
+    /// The keys and description of each action, with a heading for each
+    /// category.
+    lines: Vec<HelpLine>,
+    scroll: usize,
+    /// The number of lines that fit into the popup.
+    height: usize,
+}
+
+#[derive(Debug)]
+enum HelpLine {
+    Heading(&'static str),
+    Entry { keys: String, description: String },
+    Blank,
+}
+
+impl HelpOverlay {
+    /// Creates the overlay for the actions bound in `keymap`.
+    pub fn new(keymap: &Keymap) -> Self {
+        let mut lines = Vec::new();
+        for category in Category::ALL {
+            if !lines.is_empty() {
+                lines.push(HelpLine::Blank);
+            }
+            lines.push(HelpLine::Heading(category.title()));
+            for action in Action::ALL {
+                if action.category() != category {
+                    continue;
+                }
+                let keys = keymap.keys(action);
+                lines.push(HelpLine::Entry {
+                    keys: if keys.is_empty() {
+                        "unbound".to_string()
+                    } else {
+                        keys.join(", ")
+                    },
+                    description: action.description().to_string(),
+                });
+            }
+        }
+        Self {
+            lines,
+            scroll: 0,
+            height: 0,
+        }
+    }
+
+    /// Performs an action of the overlay, returning whether it stays open.
+    pub fn perform(&mut self, action: Action) -> bool {
+        let max_scroll = self.lines.len().saturating_sub(self.height);
+        match action {
+            Action::HelpClose => return false,
+            Action::HelpUp => self.scroll = self.scroll.saturating_sub(1),
+            Action::HelpDown => self.scroll = (self.scroll + 1).min(max_scroll),
+            Action::HelpPageUp => self.scroll = self.scroll.saturating_sub(self.height),
+            Action::HelpPageDown => self.scroll = (self.scroll + self.height).min(max_scroll),
+            Action::HelpTop => self.scroll = 0,
+            Action::HelpBottom => self.scroll = max_scroll,
+            _ => {}
+        }
+        true
+    }
+
+    /// Renders the overlay as a popup in the middle of `area`.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let keys_width = self
+            .lines
+            .iter()
+            .map(|line| match line {
+                HelpLine::Entry { keys, .. } => keys.chars().count(),
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0);
+        let width = area.width.min(80);
+        let height = area.height.min(self.lines.len() as u16 + 2);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        self.height = height.saturating_sub(2) as usize;
+        self.scroll = self
+            .scroll
+            .min(self.lines.len().saturating_sub(self.height));
+
+        let lines: Vec<Line> = self
+            .lines
+            .iter()
+            .skip(self.scroll)
+            .take(self.height)
+            .map(|line| match line {
+                HelpLine::Heading(title) => Line::styled(*title, theme.header),
+                HelpLine::Entry { keys, description } => Line::from(vec![
+                    Span::styled(
+                        format!("  {:<width$}  ", keys, width = keys_width),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(description.as_str()),
+                ]),
+                HelpLine::Blank => Line::default(),
+            })
+            .collect();
+
+        let bottom = if self.lines.len() > self.height {
+            format!(
+                "{}-{} of {}",
+                self.scroll + 1,
+                (self.scroll + self.height).min(self.lines.len()),
+                self.lines.len()
+            )
+        } else {
+            String::new()
+        };
+        let block = Block::bordered()
+            .border_style(theme.border)
+            .title("Help")
+            .title_bottom(bottom);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use anyhow::Result;
+use rusqlite::{Connection, params};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+/// A local database of completed downloads.
+///
+/// Clones share the same database connection.
+#[derive(Debug, Clone)]
+pub struct History {
+    conn: Arc<Mutex<Connection>>,
+}
+
+/// A completed download of an attachment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The base URL of the Jira instance.
+    pub instance: String,
+    /// The key of the issue the attachment belongs to.
+    pub issue: String,
+    /// The Jira id of the attachment.
+    pub attachment_id: String,
+    /// The size of the downloaded file in bytes.
+    pub size: u64,
+    /// The hex encoded SHA-256 hash of the downloaded file.
+    pub sha256: String,
+    /// The absolute path of the downloaded file.
+    pub path: PathBuf,
+    /// When the download completed, in RFC 3339 format.
+    pub timestamp: String,
+}
+
+impl History {
+    /// Opens the database at `path`, creating it if it does not exist.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to open history database {:?}: {}", path, e))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS downloads (
+                id INTEGER PRIMARY KEY,
+                instance TEXT NOT NULL,
+                issue TEXT NOT NULL,
+                attachment_id TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                sha256 TEXT NOT NULL,
+                path TEXT NOT NULL,
+                timestamp TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS downloads_attachment
+                ON downloads (instance, attachment_id);
+            CREATE TABLE IF NOT EXISTS recent_issues (
+                instance TEXT NOT NULL,
+                issue TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                PRIMARY KEY (instance, issue)
+            );",
+        )?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Locks the database connection, failing if a thread panicked while
+    /// holding it.
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|_| anyhow::anyhow!("History database lock poisoned"))
+    }
+
+    /// Adds a completed download.
+    pub fn insert(&self, record: &Record) -> Result<()> {
+        debug!(
+            "Recording download of attachment {} at {:?}",
+            record.attachment_id, record.path
+        );
+        self.conn()?.execute(
+            "INSERT INTO downloads
+                (instance, issue, attachment_id, size, sha256, path, timestamp)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                record.instance,
+                record.issue,
+                record.attachment_id,
+                record.size,
+                record.sha256,
+                record.path.to_string_lossy(),
+                record.timestamp,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Hashes the file an attachment has been downloaded to and records it.
+    pub async fn record_download(
+        &self,
+        instance: &str,
+        issue: &str,
+        attachment_id: &str,
+        path: &Path,
+    ) -> Result<Record> {
+        let path = std::path::absolute(path)?;
+        let (size, sha256) = {
+            let path = path.clone();
+            tokio::task::spawn_blocking(move || hash_file(&path)).await??
+        };
+        let record = Record {
+            instance: instance.to_string(),
+            issue: issue.to_string(),
+            attachment_id: attachment_id.to_string(),
+            size,
+            sha256,
+            path,
+            timestamp: chrono::Local::now().to_rfc3339(),
+        };
+        self.insert(&record)?;
+        Ok(record)
+    }
+
+    /// Returns the most recent download of an attachment whose file still
+    /// exists unchanged in size.
+    pub fn find_existing(&self, instance: &str, attachment_id: &str) -> Result<Option<Record>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT issue, size, sha256, path, timestamp FROM downloads
+                WHERE instance = ?1 AND attachment_id = ?2
+                ORDER BY id DESC",
+        )?;
+        let mut rows = stmt.query(params![instance, attachment_id])?;
+        while let Some(row) = rows.next()? {
+            let record = Record {
+                instance: instance.to_string(),
+                issue: row.get(0)?,
+                attachment_id: attachment_id.to_string(),
+                size: row.get(1)?,
+                sha256: row.get(2)?,
+                path: PathBuf::from(row.get::<_, String>(3)?),
+                timestamp: row.get(4)?,
+            };
+            if std::fs::metadata(&record.path).is_ok_and(|m| m.len() == record.size) {
+                return Ok(Some(record));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Marks an issue as the most recently opened one.
+    pub fn add_recent_issue(&self, instance: &str, issue: &str) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT OR REPLACE INTO recent_issues (instance, issue, timestamp)
+                VALUES (?1, ?2, ?3)",
+            params![instance, issue, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the keys of the at most `limit` most recently opened issues,
+    /// most recent first.
+    pub fn recent_issues(&self, instance: &str, limit: usize) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT issue FROM recent_issues
+                WHERE instance = ?1
+                ORDER BY timestamp DESC
+                LIMIT ?2",
+        )?;
+        let issues = stmt
+            .query_map(params![instance, limit], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(issues)
+    }
+
+    /// Returns all recorded downloads of files with the hash `sha256`.
+    pub fn find_by_hash(&self, sha256: &str) -> Result<Vec<Record>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT instance, issue, attachment_id, size, path, timestamp FROM downloads
+                WHERE sha256 = ?1",
+        )?;
+        let records = stmt
+            .query_map(params![sha256], |row| {
+                Ok(Record {
+                    instance: row.get(0)?,
+                    issue: row.get(1)?,
+                    attachment_id: row.get(2)?,
+                    size: row.get(3)?,
+                    sha256: sha256.to_string(),
+                    path: PathBuf::from(row.get::<_, String>(4)?),
+                    timestamp: row.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(records)
+    }
+}
+
+/// Returns the size and the hex encoded SHA-256 hash of a file.
+pub fn hash_file(path: &Path) -> Result<(u64, String)> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1 << 20];
+    let mut size = 0;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    let sha256 = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok((size, sha256))
+}
@@ -0,0 +1,173 @@
+use std::{process::Stdio, time::Duration};
+
+use anyhow::Result;
+use globset::{GlobBuilder, GlobMatcher};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tracing::{error, info};
+
+use crate::app::DownloadJob;
+
+/// How long a hook may run before it is killed, unless configured otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The most bytes kept of the standard output and of the standard error of
+/// a hook, the rest is discarded.
+const MAX_OUTPUT: usize = 64 << 10;
+
+/// A command run after an attachment matching its pattern has been
+/// downloaded.
+#[derive(Debug, Clone)]
+pub struct Hook {
+    matcher: GlobMatcher,
+    command: String,
+    timeout: Duration,
+}
+
+/// The outcome of running a hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookOutput {
+    /// The command that has been run.
+    pub command: String,
+    /// Whether the command exited successfully.
+    pub success: bool,
+    /// The exit status of the command, or why it could not be run.
+    pub status: String,
+    /// The standard output of the command followed by its standard error.
+    pub output: String,
+}
+
+impl Hook {
+    /// Creates a hook running `command` for downloaded files whose name
+    /// matches the glob `pattern`, ignoring case. The command is killed if it
+    /// runs longer than `timeout`.
+    pub fn new(pattern: &str, command: String, timeout: Duration) -> Result<Self> {
+        let matcher = GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()?
+            .compile_matcher();
+        Ok(Self {
+            matcher,
+            command,
+            timeout,
+        })
+    }
+
+    /// Runs the command in the shell, in the folder of the downloaded file.
+    ///
+    /// The details of the download are passed in `JIRA_DOWNLOADER_*`
+    /// environment variables.
+    pub async fn run(&self, base_url: &str, job: &DownloadJob) -> HookOutput {
+        let mut command = shell_command(&self.command);
+        command
+            .env("JIRA_DOWNLOADER_FILE", &job.file_path)
+            .env("JIRA_DOWNLOADER_ISSUE", &job.issue)
+            .env("JIRA_DOWNLOADER_BASE_URL", base_url)
+            .env("JIRA_DOWNLOADER_ATTACHMENT_ID", &job.attachment_id)
+            .env("JIRA_DOWNLOADER_FILENAME", &job.filename)
+            .env("JIRA_DOWNLOADER_SIZE", job.size.to_string())
+            .env("JIRA_DOWNLOADER_URL", &job.url)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(dir) = job.file_path.parent() {
+            command.current_dir(dir);
+        }
+
+        let failed = |status: String| HookOutput {
+            command: self.command.clone(),
+            success: false,
+            status,
+            output: String::new(),
+        };
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => return failed(format!("failed to run: {}", e)),
+        };
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let run = async {
+            let (stdout, stderr, status) =
+                tokio::join!(read_limited(stdout), read_limited(stderr), child.wait());
+            (stdout, stderr, status)
+        };
+
+        match tokio::time::timeout(self.timeout, run).await {
+            Ok((stdout, stderr, Ok(status))) => HookOutput {
+                command: self.command.clone(),
+                success: status.success(),
+                status: status.to_string(),
+                output: stdout + &stderr,
+            },
+            Ok((_, _, Err(e))) => failed(format!("failed to wait for it: {}", e)),
+            Err(_) => {
+                let _ = child.kill().await;
+                failed(format!("killed after {} s", self.timeout.as_secs()))
+            }
+        }
+    }
+}
+
+/// Runs the hooks matching the downloaded file of `job` one after another,
+/// logging their outcome.
+pub async fn run_hooks(hooks: &[Hook], base_url: &str, job: &DownloadJob) -> Vec<HookOutput> {
+    let Some(filename) = job.file_path.file_name() else {
+        return Vec::new();
+    };
+
+    let mut outputs = Vec::new();
+    for hook in hooks.iter().filter(|hook| hook.matcher.is_match(filename)) {
+        let output = hook.run(base_url, job).await;
+        if output.success {
+            info!(
+                "Hook `{}` for {:?} finished: {}\n{}",
+                output.command, job.file_path, output.status, output.output
+            );
+        } else {
+            error!(
+                "Hook `{}` for {:?} failed: {}\n{}",
+                output.command, job.file_path, output.status, output.output
+            );
+        }
+        outputs.push(output);
+    }
+    outputs
+}
+
+/// Reads the output of a hook until it ends, keeping at most `MAX_OUTPUT`
+/// bytes.
+async fn read_limited(reader: Option<impl AsyncRead + Unpin>) -> String {
+    let Some(mut reader) = reader else {
+        return String::new();
+    };
+    let mut output = Vec::new();
+    let mut truncated = false;
+    let mut buf = [0; 8192];
+    // keep reading, so the hook does not block on a full pipe
+    while let Ok(n) = reader.read(&mut buf).await
+        && n > 0
+    {
+        let keep = n.min(MAX_OUTPUT - output.len());
+        output.extend_from_slice(&buf[..keep]);
+        truncated |= keep < n;
+    }
+    let mut output = String::from_utf8_lossy(&output).into_owned();
+    if truncated {
+        output.push_str("\n[output truncated]\n");
+    }
+    output
+}
+
+#[cfg(unix)]
+fn shell_command(command: &str) -> tokio::process::Command {
+    let mut cmd = tokio::process::Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> tokio::process::Command {
+    let mut cmd = tokio::process::Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
@@ -0,0 +1,63 @@
+//! Helpers shared with the `jira-downloader` binary.
+//!
+//! They are not part of the public API and may change or disappear in any
+//! release.
+
+pub use crate::download::{decompress_to_file, part_path, resume_to_file};
+
+/// Parses a human readable size such as `"512K"`, `"1.5 MB"` or `"2048"`
+/// into a byte count. Units are binary and case-insensitive, the `B` suffix
+/// is optional.
+///
+/// # Errors
+///
+/// Returns an error if the number or the unit cannot be parsed.
+pub fn parse_file_size(size: &str) -> anyhow::Result<u64> {
+    const UNITS: &[&str] = &["", "K", "M", "G", "T", "P"];
+    let size = size.trim().to_ascii_uppercase();
+    let size = size.strip_suffix('B').unwrap_or(&size);
+    let split = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid size: {:?}", size))?;
+    let exponent = UNITS
+        .iter()
+        .position(|u| *u == unit.trim())
+        .ok_or_else(|| anyhow::anyhow!("Invalid size unit: {:?}", unit))?;
+
+    Ok((number * 1024f64.powi(exponent as i32)) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_file_size_with_units() {
+        assert_eq!(parse_file_size("512").unwrap(), 512);
+        assert_eq!(parse_file_size("512K").unwrap(), 512 << 10);
+        assert_eq!(parse_file_size("2M").unwrap(), 2 << 20);
+        assert_eq!(parse_file_size("1G").unwrap(), 1 << 30);
+        assert_eq!(parse_file_size("1.5K").unwrap(), 1536);
+    }
+
+    #[test]
+    fn parse_file_size_ignores_case_spaces_and_bytes_suffix() {
+        assert_eq!(parse_file_size("2mb").unwrap(), 2 << 20);
+        assert_eq!(parse_file_size(" 64 K ").unwrap(), 64 << 10);
+        assert_eq!(parse_file_size("100B").unwrap(), 100);
+    }
+
+    #[test]
+    fn parse_file_size_rejects_invalid_sizes() {
+        assert!(parse_file_size("").is_err());
+        assert!(parse_file_size("K").is_err());
+        assert!(parse_file_size("abc").is_err());
+        assert!(parse_file_size("12Q").is_err());
+        assert!(parse_file_size("1.2.3M").is_err());
+    }
+}
@@ -1,8 +1,15 @@
+//! Jira REST API client.
+
 use anyhow::Result;
 use futures::stream::StreamExt;
-use reqwest::{Client, IntoUrl};
-use serde::Deserialize;
-use tokio::{io::AsyncWriteExt, sync::watch::Sender};
+use reqwest::{Client, IntoUrl, StatusCode};
+use serde::{Deserialize, Deserializer};
+use tokio::{
+    io::{AsyncSeekExt, AsyncWrite, AsyncWriteExt},
+    sync::watch::Sender,
+};
+
+use crate::{compression::Compression, ratelimit::RateLimiter};
 
 /// A client for requesting Jira issue attachments.
 ///
@@ -12,17 +19,27 @@ pub struct Jira {
     client: Client,
     base_url: String,
     auth: Auth,
+    rate_limiters: Vec<RateLimiter>,
 }
 
 /// Authentication method for Jira API requests.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum Auth {
     /// No authentication.
     None,
     /// Basic authentication with username and optional password.
-    Basic { username: String, password: Option<String> },
+    Basic {
+        /// The user name.
+        username: String,
+        /// The password or API token of the user.
+        password: Option<String>,
+    },
     /// Bearer token authentication.
-    Bearer { token: String },
+    Bearer {
+        /// The personal access token.
+        token: String,
+    },
 }
 
 #[derive(Debug, Deserialize)]
@@ -37,7 +54,10 @@ struct Fields {
 
 /// Represents a file attachment from a Jira issue.
 #[derive(Debug, Deserialize, Clone)]
+#[non_exhaustive]
 pub struct Attachment {
+    /// The id of the attachment.
+    pub id: String,
     /// The filename of the attachment.
     pub filename: String,
     /// The size of the attachment in bytes.
@@ -46,19 +66,137 @@ pub struct Attachment {
     pub created: String,
     /// The content URL of the attachment.
     pub content: String,
+    /// The display name of the user who added the attachment, if known.
+    #[serde(default, deserialize_with = "display_name")]
+    pub author: Option<String>,
+    /// The MIME type of the attachment, if known.
+    #[serde(default, rename = "mimeType")]
+    pub mime_type: Option<String>,
+}
+
+/// A Jira project.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Project {
+    /// The key of the project, the prefix of its issue keys.
+    pub key: String,
+    /// The name of the project.
+    pub name: String,
+}
+
+/// A saved issue filter.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Filter {
+    /// The id of the filter.
+    pub id: String,
+    /// The name of the filter.
+    pub name: String,
+    /// The JQL query of the filter.
+    pub jql: String,
+}
+
+/// An issue found by a search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IssueSummary {
+    /// The key of the issue.
+    pub key: String,
+    /// The summary of the issue.
+    pub summary: String,
+    /// The name of the status of the issue.
+    pub status: String,
+    /// The number of attachments of the issue.
+    pub attachments: usize,
+}
+
+/// A sprint of an agile board.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Sprint {
+    /// The id of the sprint.
+    pub id: u64,
+    /// The name of the sprint.
+    pub name: String,
+    /// The state of the sprint: `future`, `active` or `closed`.
+    pub state: String,
+}
+
+/// A page of results of the agile API.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", bound(deserialize = "T: Deserialize<'de>"))]
+struct AgilePage<T> {
+    #[serde(default, alias = "issues")]
+    values: Vec<T>,
+    #[serde(default)]
+    is_last: Option<bool>,
+    #[serde(default)]
+    total: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SprintIssue {
+    key: String,
+    fields: Fields,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResults {
+    issues: Vec<SearchIssue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchIssue {
+    key: String,
+    fields: SearchFields,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchFields {
+    #[serde(default)]
+    summary: String,
+    status: Option<Status>,
+    #[serde(default)]
+    attachment: Vec<serde::de::IgnoredAny>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Status {
+    name: String,
+}
+
+fn display_name<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct User {
+        display_name: String,
+    }
+
+    Ok(Option::<User>::deserialize(deserializer)?.map(|user| user.display_name))
 }
 
 /// Events emitted during the download of an attachment.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum DownloadEvent {
     /// Download is starting.
     Starting,
     /// Download is in progress.
-    Progress { downloaded: u64, total: Option<u64> },
+    Progress {
+        /// Number of bytes downloaded so far.
+        downloaded: u64,
+        /// Total size in bytes, if reported by the server.
+        total: Option<u64>,
+    },
     /// Download has finished.
     Finished,
     /// An error occurred during download.
-    Error { msg: String },
+    Error {
+        /// Description of the error.
+        msg: String,
+    },
 }
 
 impl Jira {
@@ -73,9 +211,37 @@ impl Jira {
             client: Client::new(),
             base_url,
             auth,
+            rate_limiters: Vec::new(),
         }
     }
 
+    /// Returns the base URL of the Jira instance.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Throttles all downloads of this client with the given rate limiter.
+    ///
+    /// Limiters stack: a download is only as fast as the slowest limiter
+    /// allows. Clones of the client made afterwards share the limiter, while
+    /// limiters added to a clone do not affect the original client. This allows
+    /// combining a global limit with a limit per download:
+    ///
+    /// ```
+    /// use jira_downloader::{Auth, Jira, RateLimiter};
+    ///
+    /// let global = RateLimiter::new(Some(10 * 1024 * 1024));
+    /// let jira = Jira::new("https://jira.example.com".to_string(), Auth::None)
+    ///     .with_rate_limiter(global.clone());
+    ///
+    /// // Additionally limit a single download to 1 MB/s
+    /// let single = jira.clone().with_rate_limiter(RateLimiter::new(Some(1024 * 1024)));
+    /// ```
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiters.push(limiter);
+        self
+    }
+
     fn request(&self, url: impl IntoUrl) -> reqwest::RequestBuilder {
         let req = self.client.get(url);
         match &self.auth {
@@ -117,9 +283,190 @@ impl Jira {
         Ok(issue.fields.attachment)
     }
 
+    /// Fetches the projects visible to the user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails.
+    pub async fn fetch_projects(&self) -> Result<Vec<Project>> {
+        let url = format!("{}/rest/api/2/project", self.base_url.trim_end_matches('/'));
+        let res = self.request(&url).send().await?;
+        if !res.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to fetch projects: {}",
+                res.status()
+            ));
+        }
+        Ok(res.json().await?)
+    }
+
+    /// Fetches the filters the user has marked as favourite.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails.
+    pub async fn fetch_favourite_filters(&self) -> Result<Vec<Filter>> {
+        let url = format!(
+            "{}/rest/api/2/filter/favourite",
+            self.base_url.trim_end_matches('/')
+        );
+        let res = self.request(&url).send().await?;
+        if !res.status().is_success() {
+            return Err(anyhow::anyhow!("Failed to fetch filters: {}", res.status()));
+        }
+        Ok(res.json().await?)
+    }
+
+    /// Searches for the issues matching a JQL query, returning at most
+    /// `max_results` of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails, e.g. because the query is
+    /// invalid.
+    pub async fn search_issues(&self, jql: &str, max_results: usize) -> Result<Vec<IssueSummary>> {
+        let mut url = reqwest::Url::parse(&format!(
+            "{}/rest/api/2/search",
+            self.base_url.trim_end_matches('/')
+        ))?;
+        url.query_pairs_mut()
+            .append_pair("jql", jql)
+            .append_pair("fields", "summary,status,attachment")
+            .append_pair("maxResults", &max_results.to_string());
+        let res = self.request(url).send().await?;
+        if !res.status().is_success() {
+            return Err(anyhow::anyhow!("Failed to search issues: {}", res.status()));
+        }
+        let results: SearchResults = res.json().await?;
+        Ok(results
+            .issues
+            .into_iter()
+            .map(|issue| IssueSummary {
+                key: issue.key,
+                summary: issue.fields.summary,
+                status: issue
+                    .fields
+                    .status
+                    .map(|status| status.name)
+                    .unwrap_or_default(),
+                attachments: issue.fields.attachment.len(),
+            })
+            .collect())
+    }
+
+    /// Fetches the sprints of an agile board.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails or the board is not found.
+    pub async fn fetch_sprints(&self, board: u64) -> Result<Vec<Sprint>> {
+        let url = format!(
+            "{}/rest/agile/1.0/board/{}/sprint",
+            self.base_url.trim_end_matches('/'),
+            board
+        );
+        self.fetch_agile_pages(&url, &[], "sprints").await
+    }
+
+    /// Fetches the keys and attachments of the issues in a sprint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API request fails or the sprint is not found.
+    pub async fn fetch_sprint_attachments(
+        &self,
+        sprint: u64,
+    ) -> Result<Vec<(String, Vec<Attachment>)>> {
+        let url = format!(
+            "{}/rest/agile/1.0/sprint/{}/issue",
+            self.base_url.trim_end_matches('/'),
+            sprint
+        );
+        let issues: Vec<SprintIssue> = self
+            .fetch_agile_pages(&url, &[("fields", "attachment")], "sprint issues")
+            .await?;
+        Ok(issues
+            .into_iter()
+            .map(|issue| (issue.key, issue.fields.attachment))
+            .collect())
+    }
+
+    /// Fetches all pages of a list from the agile API, which returns at most
+    /// 50 entries per request.
+    async fn fetch_agile_pages<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        what: &str,
+    ) -> Result<Vec<T>> {
+        let mut values = Vec::new();
+        loop {
+            let mut page_url = reqwest::Url::parse(url)?;
+            page_url
+                .query_pairs_mut()
+                .extend_pairs(query)
+                .append_pair("startAt", &values.len().to_string());
+            let res = self.request(page_url).send().await?;
+            if !res.status().is_success() {
+                return Err(anyhow::anyhow!(
+                    "Failed to fetch {}: {}",
+                    what,
+                    res.status()
+                ));
+            }
+            let page: AgilePage<T> = res.json().await?;
+            let empty = page.values.is_empty();
+            values.extend(page.values);
+            let last = page
+                .is_last
+                .unwrap_or_else(|| page.total.is_none_or(|total| values.len() >= total));
+            if last || empty {
+                return Ok(values);
+            }
+        }
+    }
+
+    /// Fetches the first `len` bytes of an attachment, e.g. for a preview.
+    ///
+    /// Uses a range request, so servers supporting it only send the requested
+    /// part. Fewer bytes are returned if the attachment is shorter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails.
+    pub async fn fetch_prefix(&self, url: &str, len: u64) -> Result<Vec<u8>> {
+        let resp = self
+            .request(url)
+            .header(
+                reqwest::header::RANGE,
+                format!("bytes=0-{}", len.max(1) - 1),
+            )
+            .send()
+            .await?;
+        if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            // The attachment is empty
+            return Ok(Vec::new());
+        }
+        if !resp.status().is_success() {
+            return Err(anyhow::anyhow!("HTTP error: {}", resp.status()));
+        }
+
+        let len = len as usize;
+        let mut data = Vec::new();
+        let mut stream = resp.bytes_stream();
+        while data.len() < len
+            && let Some(chunk) = stream.next().await
+        {
+            let chunk = chunk?;
+            data.extend_from_slice(&chunk[..chunk.len().min(len - data.len())]);
+        }
+        Ok(data)
+    }
+
     /// Downloads an attachment and writes it to a file.
     ///
-    /// Progress updates are sent through the provided channel.
+    /// Progress updates are sent through the provided channel. The download
+    /// is throttled by the rate limiters of the client.
     ///
     /// # Arguments
     ///
@@ -135,41 +482,167 @@ impl Jira {
     ///
     /// Returns an error if the download fails or the file write operation fails.
     pub async fn download_attachment(
+        &self,
+        url: String,
+        file: tokio::fs::File,
+        tx: Sender<DownloadEvent>,
+    ) -> Result<()> {
+        self.resume_attachment(url, file, 0, tx).await
+    }
+
+    /// Continues a partial download of an attachment.
+    ///
+    /// Requests the content after the first `offset` bytes and writes it to
+    /// `file` starting at `offset`, so a download interrupted before can be
+    /// completed without fetching its beginning again. If the server does not
+    /// support range requests, the whole attachment is downloaded and `file`
+    /// is overwritten. Progress is reported including the first `offset` bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the attachment to download
+    /// * `file` - The file holding the first `offset` bytes of the attachment
+    /// * `offset` - The number of bytes already downloaded
+    /// * `tx` - A channel sender for download progress events
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the download fails or the file write operation fails.
+    pub async fn resume_attachment(
         &self,
         url: String,
         mut file: tokio::fs::File,
+        offset: u64,
         tx: Sender<DownloadEvent>,
     ) -> Result<()> {
-        let req = self.request(&url);
-        let resp = req.send().await?;
+        let mut resp = self.range_request(&url, offset).send().await?;
+        if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+            // The partial file does not fit the attachment, start over
+            resp = self.range_request(&url, 0).send().await?;
+        }
         if !resp.status().is_success() {
             return Err(anyhow::anyhow!("HTTP error: {}", resp.status()));
         }
-        let total = resp.content_length();
+        let offset = if resp.status() == StatusCode::PARTIAL_CONTENT {
+            offset
+        } else {
+            0
+        };
+        file.set_len(offset).await?;
+        file.seek(std::io::SeekFrom::Start(offset)).await?;
+
+        let result = self.write_content(resp, &mut file, offset, &tx).await;
+        // Also keep what has been received when cancelled, so it can be resumed
+        file.flush().await?;
+        result?;
+
+        let _ = tx.send(DownloadEvent::Finished);
+        Ok(())
+    }
+
+    /// Downloads a compressed attachment, decompressing it on the fly.
+    ///
+    /// The decompressed content is written to `file`. Progress is reported in
+    /// compressed bytes, and the download fails unless exactly `size`
+    /// compressed bytes have been received and form a complete compressed
+    /// stream. Decompressed downloads cannot be resumed.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the attachment to download
+    /// * `file` - The file to write the decompressed content to
+    /// * `compression` - The compression format of the attachment
+    /// * `size` - The compressed size of the attachment as reported by Jira
+    /// * `tx` - A channel sender for download progress events
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the download fails, the size does not match, the
+    /// content cannot be decompressed or the file write operation fails.
+    pub async fn download_decompressed(
+        &self,
+        url: String,
+        file: tokio::fs::File,
+        compression: Compression,
+        size: u64,
+        tx: Sender<DownloadEvent>,
+    ) -> Result<()> {
+        let resp = self.request(&url).send().await?;
+        if !resp.status().is_success() {
+            return Err(anyhow::anyhow!("HTTP error: {}", resp.status()));
+        }
+
+        let mut decoder = compression.decoder(file);
+        let downloaded = self.write_content(resp, &mut decoder, 0, &tx).await?;
+        if downloaded != size {
+            return Err(anyhow::anyhow!(
+                "Received {} bytes instead of {}",
+                downloaded,
+                size
+            ));
+        }
+        // Fails if the compressed stream is incomplete
+        decoder
+            .shutdown()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to decompress: {}", e))?;
+
+        let _ = tx.send(DownloadEvent::Finished);
+        Ok(())
+    }
+
+    /// Writes the content of a response to `writer`, returning the number of
+    /// bytes downloaded including the first `offset` bytes.
+    async fn write_content(
+        &self,
+        resp: reqwest::Response,
+        writer: &mut (impl AsyncWrite + Unpin),
+        offset: u64,
+        tx: &Sender<DownloadEvent>,
+    ) -> Result<u64> {
+        let total = resp.content_length().map(|len| len + offset);
         let mut stream = resp.bytes_stream();
-        let mut downloaded: u64 = 0;
+        let mut downloaded: u64 = offset;
 
         loop {
+            let next_chunk = async {
+                let chunk = stream.next().await;
+                if let Some(Ok(chunk)) = &chunk {
+                    for limiter in &self.rate_limiters {
+                        limiter.acquire(chunk.len() as u64).await;
+                    }
+                }
+                chunk
+            };
+
             tokio::select! {
                 _ = tx.closed() => {
                     // Download cancelled
                     break Err(anyhow::anyhow!("Download cancelled"))
                 }
-                chunk = stream.next() => {
+                chunk = next_chunk => {
                     if let Some(chunk) = chunk {
                         let chunk = chunk?;
                         downloaded += chunk.len() as u64;
-                        file.write_all(&chunk).await?;
+                        writer.write_all(&chunk).await?;
                         let _ = tx.send(DownloadEvent::Progress {
                             downloaded,
                             total,
                         });
                     } else {
-                        let _ = tx.send(DownloadEvent::Finished);
-                        break Ok(())
+                        break Ok(downloaded)
                     }
                 }
             }
         }
     }
+
+    fn range_request(&self, url: &str, offset: u64) -> reqwest::RequestBuilder {
+        let req = self.request(url);
+        if offset > 0 {
+            req.header(reqwest::header::RANGE, format!("bytes={}-", offset))
+        } else {
+            req
+        }
+    }
 }
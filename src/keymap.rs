@@ -0,0 +1,1052 @@
+use std::{collections::HashMap, fmt};
+
+use anyhow::{Result, anyhow};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Something the user can do in the attachment table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    ExtendUp,
+    ExtendDown,
+    Visual,
+    ToggleQueued,
+    SelectAll,
+    SelectNone,
+    InvertSelection,
+    SelectMatching,
+    StartDownloads,
+    Pause,
+    Cancel,
+    CopyFromHistory,
+    LinkFromHistory,
+    Extract,
+    OpenFile,
+    OpenFolder,
+    Edit,
+    View,
+    Preview,
+    Details,
+    GotoIssue,
+    BrowseIssues,
+    PreviousIssue,
+    NextIssue,
+    Sort,
+    ReverseSort,
+    Filter,
+    RaiseRateLimit,
+    LowerRateLimit,
+    Back,
+    ToggleSelection,
+    Help,
+    /// Switches to the issue tab with the given number, from 1 to 9.
+    SwitchIssue(u8),
+    RestoreQueue,
+    DiscardQueue,
+    PreviewClose,
+    PreviewUp,
+    PreviewDown,
+    PreviewPageUp,
+    PreviewPageDown,
+    PreviewTop,
+    PreviewBottom,
+    PreviewSearch,
+    PreviewNextMatch,
+    PreviewPreviousMatch,
+    BrowserClose,
+    BrowserUp,
+    BrowserDown,
+    BrowserSwitchPane,
+    BrowserEditQuery,
+    BrowserOpen,
+    HelpClose,
+    HelpUp,
+    HelpDown,
+    HelpPageUp,
+    HelpPageDown,
+    HelpTop,
+    HelpBottom,
+}
+
+/// Where keys are looked up: the attachment table or one of the prompts and
+/// popups shown on top of it, each with its own bindings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    /// The prompt asking whether to restore the downloads of the last
+    /// session.
+    Prompt,
+    Preview,
+    Browser,
+    Help,
+}
+
+/// A group of related actions in the help overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    General,
+    Navigation,
+    Queue,
+    Downloads,
+    Files,
+    View,
+    Issues,
+    Prompt,
+    Preview,
+    Browser,
+    HelpOverlay,
+}
+
+impl Category {
+    /// All categories, in the order they are listed.
+    pub const ALL: [Category; 11] = [
+        Category::General,
+        Category::Navigation,
+        Category::Queue,
+        Category::Downloads,
+        Category::Files,
+        Category::View,
+        Category::Issues,
+        Category::Prompt,
+        Category::Preview,
+        Category::Browser,
+        Category::HelpOverlay,
+    ];
+
+    /// Returns the heading of the category in the help overlay.
+    pub fn title(self) -> &'static str {
+        match self {
+            Category::General => "General",
+            Category::Navigation => "Navigation",
+            Category::Queue => "Queue",
+            Category::Downloads => "Downloads",
+            Category::Files => "Files",
+            Category::View => "View",
+            Category::Issues => "Issues",
+            Category::Prompt => "Restore Prompt",
+            Category::Preview => "Preview",
+            Category::Browser => "Issue Browser",
+            Category::HelpOverlay => "Help Overlay",
+        }
+    }
+}
+
+/// The bindings of the default keymap.
+const DEFAULT_KEYS: &[(&str, Action)] = &[
+    ("q", Action::Quit),
+    ("Up", Action::Up),
+    ("Down", Action::Down),
+    ("PageUp", Action::PageUp),
+    ("PageDown", Action::PageDown),
+    ("Home", Action::First),
+    ("End", Action::Last),
+    ("Shift+Up", Action::ExtendUp),
+    ("Shift+Down", Action::ExtendDown),
+    ("Space", Action::ToggleQueued),
+    ("a", Action::SelectAll),
+    ("n", Action::SelectNone),
+    ("i", Action::InvertSelection),
+    ("*", Action::SelectMatching),
+    ("Enter", Action::StartDownloads),
+    ("p", Action::Pause),
+    ("c", Action::Cancel),
+    ("C", Action::CopyFromHistory),
+    ("L", Action::LinkFromHistory),
+    ("x", Action::Extract),
+    ("o", Action::OpenFile),
+    ("O", Action::OpenFolder),
+    ("e", Action::Edit),
+    ("V", Action::View),
+    ("v", Action::Preview),
+    ("d", Action::Details),
+    ("g", Action::GotoIssue),
+    ("b", Action::BrowseIssues),
+    ("[", Action::PreviousIssue),
+    ("]", Action::NextIssue),
+    ("s", Action::Sort),
+    ("S", Action::ReverseSort),
+    ("/", Action::Filter),
+    ("+", Action::RaiseRateLimit),
+    ("-", Action::LowerRateLimit),
+    ("Esc", Action::Back),
+    ("Tab", Action::ToggleSelection),
+    ("?", Action::Help),
+    ("1", Action::SwitchIssue(1)),
+    ("2", Action::SwitchIssue(2)),
+    ("3", Action::SwitchIssue(3)),
+    ("4", Action::SwitchIssue(4)),
+    ("5", Action::SwitchIssue(5)),
+    ("6", Action::SwitchIssue(6)),
+    ("7", Action::SwitchIssue(7)),
+    ("8", Action::SwitchIssue(8)),
+    ("9", Action::SwitchIssue(9)),
+    ("y", Action::RestoreQueue),
+    ("n", Action::DiscardQueue),
+    ("Esc", Action::DiscardQueue),
+    ("Esc", Action::PreviewClose),
+    ("q", Action::PreviewClose),
+    ("v", Action::PreviewClose),
+    ("Up", Action::PreviewUp),
+    ("k", Action::PreviewUp),
+    ("Down", Action::PreviewDown),
+    ("j", Action::PreviewDown),
+    ("PageUp", Action::PreviewPageUp),
+    ("PageDown", Action::PreviewPageDown),
+    ("Space", Action::PreviewPageDown),
+    ("Home", Action::PreviewTop),
+    ("End", Action::PreviewBottom),
+    ("/", Action::PreviewSearch),
+    ("n", Action::PreviewNextMatch),
+    ("N", Action::PreviewPreviousMatch),
+    ("Esc", Action::BrowserClose),
+    ("q", Action::BrowserClose),
+    ("b", Action::BrowserClose),
+    ("Up", Action::BrowserUp),
+    ("Down", Action::BrowserDown),
+    ("Tab", Action::BrowserSwitchPane),
+    ("Left", Action::BrowserSwitchPane),
+    ("Right", Action::BrowserSwitchPane),
+    ("/", Action::BrowserEditQuery),
+    ("Enter", Action::BrowserOpen),
+    ("Esc", Action::HelpClose),
+    ("q", Action::HelpClose),
+    ("?", Action::HelpClose),
+    ("Up", Action::HelpUp),
+    ("k", Action::HelpUp),
+    ("Down", Action::HelpDown),
+    ("j", Action::HelpDown),
+    ("PageUp", Action::HelpPageUp),
+    ("PageDown", Action::HelpPageDown),
+    ("Home", Action::HelpTop),
+    ("End", Action::HelpBottom),
+];
+
+/// The bindings of the vim preset replacing or adding to the default ones.
+const VIM_KEYS: &[(&str, Action)] = &[
+    ("k", Action::Up),
+    ("j", Action::Down),
+    ("Ctrl+b", Action::PageUp),
+    ("Ctrl+f", Action::PageDown),
+    ("g g", Action::First),
+    ("G", Action::Last),
+    ("v", Action::Visual),
+    ("K", Action::Preview),
+    ("g i", Action::GotoIssue),
+    ("g T", Action::PreviousIssue),
+    ("g t", Action::NextIssue),
+    ("Ctrl+b", Action::PreviewPageUp),
+    ("Ctrl+f", Action::PreviewPageDown),
+    ("g g", Action::PreviewTop),
+    ("G", Action::PreviewBottom),
+    ("K", Action::PreviewClose),
+    ("k", Action::BrowserUp),
+    ("j", Action::BrowserDown),
+    ("Ctrl+b", Action::HelpPageUp),
+    ("Ctrl+f", Action::HelpPageDown),
+    ("g g", Action::HelpTop),
+    ("G", Action::HelpBottom),
+];
+
+impl Action {
+    /// All actions, in the order they are listed in the help overlay.
+    pub const ALL: [Action; 73] = [
+        Action::Quit,
+        Action::Help,
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::First,
+        Action::Last,
+        Action::ToggleSelection,
+        Action::Back,
+        Action::ToggleQueued,
+        Action::ExtendUp,
+        Action::ExtendDown,
+        Action::Visual,
+        Action::SelectAll,
+        Action::SelectNone,
+        Action::InvertSelection,
+        Action::SelectMatching,
+        Action::StartDownloads,
+        Action::Pause,
+        Action::Cancel,
+        Action::CopyFromHistory,
+        Action::LinkFromHistory,
+        Action::RaiseRateLimit,
+        Action::LowerRateLimit,
+        Action::Extract,
+        Action::OpenFile,
+        Action::OpenFolder,
+        Action::Edit,
+        Action::View,
+        Action::Preview,
+        Action::Details,
+        Action::Sort,
+        Action::ReverseSort,
+        Action::Filter,
+        Action::GotoIssue,
+        Action::BrowseIssues,
+        Action::PreviousIssue,
+        Action::NextIssue,
+        Action::SwitchIssue(1),
+        Action::SwitchIssue(2),
+        Action::SwitchIssue(3),
+        Action::SwitchIssue(4),
+        Action::SwitchIssue(5),
+        Action::SwitchIssue(6),
+        Action::SwitchIssue(7),
+        Action::SwitchIssue(8),
+        Action::SwitchIssue(9),
+        Action::RestoreQueue,
+        Action::DiscardQueue,
+        Action::PreviewClose,
+        Action::PreviewUp,
+        Action::PreviewDown,
+        Action::PreviewPageUp,
+        Action::PreviewPageDown,
+        Action::PreviewTop,
+        Action::PreviewBottom,
+        Action::PreviewSearch,
+        Action::PreviewNextMatch,
+        Action::PreviewPreviousMatch,
+        Action::BrowserClose,
+        Action::BrowserUp,
+        Action::BrowserDown,
+        Action::BrowserSwitchPane,
+        Action::BrowserEditQuery,
+        Action::BrowserOpen,
+        Action::HelpClose,
+        Action::HelpUp,
+        Action::HelpDown,
+        Action::HelpPageUp,
+        Action::HelpPageDown,
+        Action::HelpTop,
+        Action::HelpBottom,
+    ];
+
+    /// Returns the action with the given name in the config file.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the name of the action in the `[keys]` table of the config
+    /// file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::ToggleSelection => "toggle_selection",
+            Action::Back => "back",
+            Action::ToggleQueued => "toggle_queued",
+            Action::ExtendUp => "extend_up",
+            Action::ExtendDown => "extend_down",
+            Action::Visual => "visual",
+            Action::SelectAll => "select_all",
+            Action::SelectNone => "select_none",
+            Action::InvertSelection => "invert_selection",
+            Action::SelectMatching => "select_matching",
+            Action::StartDownloads => "start_downloads",
+            Action::Pause => "pause",
+            Action::Cancel => "cancel",
+            Action::CopyFromHistory => "copy_from_history",
+            Action::LinkFromHistory => "link_from_history",
+            Action::RaiseRateLimit => "raise_rate_limit",
+            Action::LowerRateLimit => "lower_rate_limit",
+            Action::Extract => "extract",
+            Action::OpenFile => "open_file",
+            Action::OpenFolder => "open_folder",
+            Action::Edit => "edit",
+            Action::View => "view",
+            Action::Preview => "preview",
+            Action::Details => "details",
+            Action::Sort => "sort",
+            Action::ReverseSort => "reverse_sort",
+            Action::Filter => "filter",
+            Action::GotoIssue => "goto_issue",
+            Action::BrowseIssues => "browse_issues",
+            Action::PreviousIssue => "previous_issue",
+            Action::NextIssue => "next_issue",
+            Action::SwitchIssue(n) => ISSUE_NAMES[n as usize - 1],
+            Action::RestoreQueue => "restore_queue",
+            Action::DiscardQueue => "discard_queue",
+            Action::PreviewClose => "preview_close",
+            Action::PreviewUp => "preview_up",
+            Action::PreviewDown => "preview_down",
+            Action::PreviewPageUp => "preview_page_up",
+            Action::PreviewPageDown => "preview_page_down",
+            Action::PreviewTop => "preview_top",
+            Action::PreviewBottom => "preview_bottom",
+            Action::PreviewSearch => "preview_search",
+            Action::PreviewNextMatch => "preview_next_match",
+            Action::PreviewPreviousMatch => "preview_previous_match",
+            Action::BrowserClose => "browser_close",
+            Action::BrowserUp => "browser_up",
+            Action::BrowserDown => "browser_down",
+            Action::BrowserSwitchPane => "browser_switch_pane",
+            Action::BrowserEditQuery => "browser_edit_query",
+            Action::BrowserOpen => "browser_open",
+            Action::HelpClose => "help_close",
+            Action::HelpUp => "help_up",
+            Action::HelpDown => "help_down",
+            Action::HelpPageUp => "help_page_up",
+            Action::HelpPageDown => "help_page_down",
+            Action::HelpTop => "help_top",
+            Action::HelpBottom => "help_bottom",
+        }
+    }
+
+    /// Returns the group of the action in the help overlay.
+    pub fn category(self) -> Category {
+        match self {
+            Action::Quit => Category::General,
+            Action::Help => Category::General,
+            Action::Up => Category::Navigation,
+            Action::Down => Category::Navigation,
+            Action::PageUp => Category::Navigation,
+            Action::PageDown => Category::Navigation,
+            Action::First => Category::Navigation,
+            Action::Last => Category::Navigation,
+            Action::ToggleSelection => Category::Navigation,
+            Action::Back => Category::Navigation,
+            Action::ToggleQueued => Category::Queue,
+            Action::ExtendUp => Category::Queue,
+            Action::ExtendDown => Category::Queue,
+            Action::Visual => Category::Queue,
+            Action::SelectAll => Category::Queue,
+            Action::SelectNone => Category::Queue,
+            Action::InvertSelection => Category::Queue,
+            Action::SelectMatching => Category::Queue,
+            Action::StartDownloads => Category::Downloads,
+            Action::Pause => Category::Downloads,
+            Action::Cancel => Category::Downloads,
+            Action::CopyFromHistory => Category::Downloads,
+            Action::LinkFromHistory => Category::Downloads,
+            Action::RaiseRateLimit => Category::Downloads,
+            Action::LowerRateLimit => Category::Downloads,
+            Action::Extract => Category::Files,
+            Action::OpenFile => Category::Files,
+            Action::OpenFolder => Category::Files,
+            Action::Edit => Category::Files,
+            Action::View => Category::Files,
+            Action::Preview => Category::View,
+            Action::Details => Category::View,
+            Action::Sort => Category::View,
+            Action::ReverseSort => Category::View,
+            Action::Filter => Category::View,
+            Action::GotoIssue => Category::Issues,
+            Action::BrowseIssues => Category::Issues,
+            Action::PreviousIssue => Category::Issues,
+            Action::NextIssue => Category::Issues,
+            Action::SwitchIssue(_) => Category::Issues,
+            Action::RestoreQueue => Category::Prompt,
+            Action::DiscardQueue => Category::Prompt,
+            Action::PreviewClose => Category::Preview,
+            Action::PreviewUp => Category::Preview,
+            Action::PreviewDown => Category::Preview,
+            Action::PreviewPageUp => Category::Preview,
+            Action::PreviewPageDown => Category::Preview,
+            Action::PreviewTop => Category::Preview,
+            Action::PreviewBottom => Category::Preview,
+            Action::PreviewSearch => Category::Preview,
+            Action::PreviewNextMatch => Category::Preview,
+            Action::PreviewPreviousMatch => Category::Preview,
+            Action::BrowserClose => Category::Browser,
+            Action::BrowserUp => Category::Browser,
+            Action::BrowserDown => Category::Browser,
+            Action::BrowserSwitchPane => Category::Browser,
+            Action::BrowserEditQuery => Category::Browser,
+            Action::BrowserOpen => Category::Browser,
+            Action::HelpClose => Category::HelpOverlay,
+            Action::HelpUp => Category::HelpOverlay,
+            Action::HelpDown => Category::HelpOverlay,
+            Action::HelpPageUp => Category::HelpOverlay,
+            Action::HelpPageDown => Category::HelpOverlay,
+            Action::HelpTop => Category::HelpOverlay,
+            Action::HelpBottom => Category::HelpOverlay,
+        }
+    }
+
+    /// Returns the modes in which the keys of the action apply.
+    pub fn modes(self) -> &'static [Mode] {
+        match self {
+            // also answers the restore prompt
+            Action::Quit => &[Mode::Normal, Mode::Prompt],
+            _ => match self.category() {
+                Category::Prompt => &[Mode::Prompt],
+                Category::Preview => &[Mode::Preview],
+                Category::Browser => &[Mode::Browser],
+                Category::HelpOverlay => &[Mode::Help],
+                _ => &[Mode::Normal],
+            },
+        }
+    }
+
+    /// Returns what the action does, for the help overlay.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Help => "Show all actions and their keys",
+            Action::Up => "Select the previous attachment",
+            Action::Down => "Select the next attachment",
+            Action::PageUp => "Move the selection up by a page",
+            Action::PageDown => "Move the selection down by a page",
+            Action::First => "Select the first attachment",
+            Action::Last => "Select the last attachment",
+            Action::ToggleSelection => "Select the first attachment or clear the selection",
+            Action::Back => "Leave visual mode, clear the filter or clear the selection",
+            Action::ToggleQueued => "Queue or unqueue the selected attachment",
+            Action::ExtendUp => "Queue the selected attachment and the previous one",
+            Action::ExtendDown => "Queue the selected attachment and the next one",
+            Action::Visual => "Queue the attachments the selection moves over",
+            Action::SelectAll => "Queue all attachments",
+            Action::SelectNone => "Unqueue all attachments",
+            Action::InvertSelection => "Invert which attachments are queued",
+            Action::SelectMatching => "Queue the attachments matching a glob pattern",
+            Action::StartDownloads => "Start downloading the queued attachments",
+            Action::Pause => "Pause or resume the selected download",
+            Action::Cancel => "Cancel the selected download",
+            Action::CopyFromHistory => "Copy the attachment downloaded to another folder before",
+            Action::LinkFromHistory => {
+                "Hard link the attachment downloaded to another folder before"
+            }
+            Action::RaiseRateLimit => "Raise the bandwidth limit",
+            Action::LowerRateLimit => "Lower the bandwidth limit",
+            Action::Extract => "Extract the selected archive",
+            Action::OpenFile => "Open the selected file",
+            Action::OpenFolder => "Open the download folder",
+            Action::Edit => "Edit the selected file in $VISUAL or $EDITOR",
+            Action::View => "View the selected file in $PAGER",
+            Action::Preview => "Preview the beginning of the selected attachment",
+            Action::Details => "Show or hide the details of the selected attachment",
+            Action::Sort => "Sort by the next column",
+            Action::ReverseSort => "Reverse the sort order",
+            Action::Filter => "Filter the attachments",
+            Action::GotoIssue => "Go to another issue",
+            Action::BrowseIssues => "Browse issues by filter or JQL query",
+            Action::PreviousIssue => "Switch to the previous issue tab",
+            Action::NextIssue => "Switch to the next issue tab",
+            Action::SwitchIssue(n) => ISSUE_DESCRIPTIONS[n as usize - 1],
+            Action::RestoreQueue => "Restore the downloads of the last session",
+            Action::DiscardQueue => "Discard the downloads of the last session",
+            Action::PreviewClose => "Close the preview",
+            Action::PreviewUp => "Scroll up by a line",
+            Action::PreviewDown => "Scroll down by a line",
+            Action::PreviewPageUp => "Scroll up by a page",
+            Action::PreviewPageDown => "Scroll down by a page",
+            Action::PreviewTop => "Scroll to the beginning",
+            Action::PreviewBottom => "Scroll to the end",
+            Action::PreviewSearch => "Search for text",
+            Action::PreviewNextMatch => "Jump to the next matching line",
+            Action::PreviewPreviousMatch => "Jump to the previous matching line",
+            Action::BrowserClose => "Return to the attachments",
+            Action::BrowserUp => "Select the previous filter or issue",
+            Action::BrowserDown => "Select the next filter or issue",
+            Action::BrowserSwitchPane => "Switch between the filters and the issues",
+            Action::BrowserEditQuery => "Edit the JQL query",
+            Action::BrowserOpen => "Run the selected filter or open the selected issue",
+            Action::HelpClose => "Close the help overlay",
+            Action::HelpUp => "Scroll up by a line",
+            Action::HelpDown => "Scroll down by a line",
+            Action::HelpPageUp => "Scroll up by a page",
+            Action::HelpPageDown => "Scroll down by a page",
+            Action::HelpTop => "Scroll to the beginning",
+            Action::HelpBottom => "Scroll to the end",
+        }
+    }
+}
+
+const ISSUE_NAMES: [&str; 9] = [
+    "issue_1", "issue_2", "issue_3", "issue_4", "issue_5", "issue_6", "issue_7", "issue_8",
+    "issue_9",
+];
+
+const ISSUE_DESCRIPTIONS: [&str; 9] = [
+    "Switch to the 1st issue tab",
+    "Switch to the 2nd issue tab",
+    "Switch to the 3rd issue tab",
+    "Switch to the 4th issue tab",
+    "Switch to the 5th issue tab",
+    "Switch to the 6th issue tab",
+    "Switch to the 7th issue tab",
+    "Switch to the 8th issue tab",
+    "Switch to the 9th issue tab",
+];
+
+/// A key with its modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Whether Shift is reported with upper case letters and symbols
+        // depends on the terminal, so it is dropped in favour of the upper
+        // case letter, e.g. `Shift+a` is `A`.
+        match code {
+            KeyCode::Char(c) => {
+                let code = if modifiers.contains(KeyModifiers::SHIFT) {
+                    let mut upper = c.to_uppercase();
+                    match (upper.next(), upper.next()) {
+                        (Some(upper), None) => KeyCode::Char(upper),
+                        _ => code,
+                    }
+                } else {
+                    code
+                };
+                Self {
+                    code,
+                    modifiers: modifiers - KeyModifiers::SHIFT,
+                }
+            }
+            _ => Self { code, modifiers },
+        }
+    }
+
+    /// Parses a key like `q`, `Enter` or `Ctrl+d`.
+    fn parse(text: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut name = text;
+        while let Some((modifier, rest)) = name.split_once('+')
+            && !rest.is_empty()
+        {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(anyhow!("Unknown modifier {} in key {}", modifier, text)),
+            };
+            name = rest;
+        }
+
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            // the character typed with Shift depends on the keyboard layout
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) && !c.is_alphabetic() => {
+                return Err(anyhow!(
+                    "Key {} cannot be bound, use the character typed with Shift instead",
+                    text
+                ));
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match name.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                lower => match lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(anyhow!("Unknown key {}", text)),
+                },
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+
+    /// Parses a sequence of keys separated by spaces, like `g g`.
+    fn parse_sequence(text: &str) -> Result<Vec<Self>> {
+        let keys = text
+            .split_whitespace()
+            .map(Self::parse)
+            .collect::<Result<Vec<_>>>()?;
+        if keys.is_empty() {
+            return Err(anyhow!("Empty key binding"));
+        }
+        Ok(keys)
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(key_evt: KeyEvent) -> Self {
+        Self::new(key_evt.code, key_evt.modifiers)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            code => write!(f, "{}", code),
+        }
+    }
+}
+
+/// A binding given in the `[keys]` table: one key sequence or a list of
+/// them.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The result of looking up the keys pressed so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+    /// The keys are bound to the action.
+    Action(Action),
+    /// The keys start a longer sequence, wait for the next one.
+    Pending,
+    /// The keys are not bound.
+    None,
+}
+
+fn shares_mode(a: Action, b: Action) -> bool {
+    a.modes().iter().any(|mode| b.modes().contains(mode))
+}
+
+/// Maps key sequences to actions.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Vec<Key>, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self {
+            bindings: Vec::new(),
+        };
+        keymap.bind_all(DEFAULT_KEYS);
+        keymap
+    }
+}
+
+impl Keymap {
+    /// Returns the built-in keymap with the given name, `default` or `vim`.
+    pub fn preset(name: &str) -> Result<Self> {
+        let mut keymap = Self::default();
+        match name.to_ascii_lowercase().as_str() {
+            "default" => {}
+            "vim" => keymap.bind_all(VIM_KEYS),
+            _ => return Err(anyhow!("Unknown keymap {}", name)),
+        }
+        Ok(keymap)
+    }
+
+    /// Replaces the keys of the actions in `keys`, a map of action names to
+    /// key sequences.
+    pub fn with_overrides(mut self, keys: &HashMap<String, KeyBinding>) -> Result<Self> {
+        for (name, binding) in keys {
+            let action = Action::from_name(name)
+                .ok_or_else(|| anyhow!("Unknown action {} in [keys]", name))?;
+            let sequences = match binding {
+                KeyBinding::One(keys) => vec![keys.clone()],
+                KeyBinding::Many(keys) => keys.clone(),
+            };
+            let sequences = sequences
+                .iter()
+                .map(|keys| Key::parse_sequence(keys))
+                .collect::<Result<Vec<_>>>()?;
+            self.bindings.retain(|(_, a)| *a != action);
+            for keys in sequences {
+                self.bind(keys, action);
+            }
+        }
+        Ok(self)
+    }
+
+    fn bind_all(&mut self, bindings: &[(&str, Action)]) {
+        for (keys, action) in bindings {
+            let keys = Key::parse_sequence(keys).expect("built-in key bindings are valid");
+            self.bind(keys, *action);
+        }
+    }
+
+    /// Binds `keys` to `action`, replacing the bindings of the same keys and
+    /// of their prefixes in the modes of `action`, which would never be
+    /// reached.
+    fn bind(&mut self, keys: Vec<Key>, action: Action) {
+        self.bindings.retain(|(bound, bound_action)| {
+            !shares_mode(action, *bound_action)
+                || !keys.starts_with(bound) && !bound.starts_with(&keys)
+        });
+        self.bindings.push((keys, action));
+    }
+
+    /// Looks up the keys pressed so far in `mode`.
+    pub fn lookup(&self, mode: Mode, keys: &[Key]) -> Lookup {
+        let mut lookup = Lookup::None;
+        for (bound, action) in &self.bindings {
+            if !action.modes().contains(&mode) {
+                continue;
+            }
+            if bound == keys {
+                return Lookup::Action(*action);
+            }
+            if bound.starts_with(keys) {
+                lookup = Lookup::Pending;
+            }
+        }
+        lookup
+    }
+
+    /// Returns all key sequences bound to `action`, e.g. `g g`.
+    pub fn keys(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(keys, _)| {
+                keys.iter()
+                    .map(Key::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    /// Returns the first key sequence bound to `action`, if any.
+    pub fn first_key(&self, action: Action) -> Option<String> {
+        self.keys(action).into_iter().next()
+    }
+
+    /// Returns a hint for the help bar like `o/O: Open File/Folder`, with
+    /// the first keys of the bound `actions`, or `None` if none is bound.
+    pub fn hint(&self, actions: &[Action], label: &str) -> Option<String> {
+        let keys: Vec<String> = actions
+            .iter()
+            .filter_map(|action| self.first_key(*action))
+            .collect();
+        (!keys.is_empty()).then(|| format!("{}: {}", keys.join("/"), label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Key {
+        KeyEvent::new(code, modifiers).into()
+    }
+
+    #[test]
+    fn parse_keys_with_modifiers() {
+        assert_eq!(
+            Key::parse("q").unwrap(),
+            key(KeyCode::Char('q'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Key::parse("Ctrl+d").unwrap(),
+            key(KeyCode::Char('d'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            Key::parse("control+alt+Enter").unwrap(),
+            key(KeyCode::Enter, KeyModifiers::CONTROL | KeyModifiers::ALT)
+        );
+        assert_eq!(
+            Key::parse("+").unwrap(),
+            key(KeyCode::Char('+'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Key::parse("Ctrl++").unwrap(),
+            key(KeyCode::Char('+'), KeyModifiers::CONTROL)
+        );
+    }
+
+    #[test]
+    fn parse_named_keys() {
+        assert_eq!(
+            Key::parse("Space").unwrap(),
+            key(KeyCode::Char(' '), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Key::parse("pgdn").unwrap(),
+            key(KeyCode::PageDown, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Key::parse("F12").unwrap(),
+            key(KeyCode::F(12), KeyModifiers::NONE)
+        );
+        assert!(Key::parse("F13").is_err());
+        assert!(Key::parse("Foo").is_err());
+        assert!(Key::parse("Hyper+a").is_err());
+    }
+
+    #[test]
+    fn parse_sequences() {
+        assert_eq!(
+            Key::parse_sequence("g  g").unwrap(),
+            vec![key(KeyCode::Char('g'), KeyModifiers::NONE); 2]
+        );
+        assert!(Key::parse_sequence(" ").is_err());
+        assert!(Key::parse_sequence("g Foo").is_err());
+    }
+
+    #[test]
+    fn keys_are_displayed_like_they_are_parsed() {
+        for text in ["q", "Ctrl+d", "Alt+Enter", "Space", "F5", "Shift+↑"] {
+            let parsed = Key::parse(&text.replace('↑', "Up")).unwrap();
+            assert_eq!(parsed.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn lookup_sequences() {
+        let keymap = Keymap::preset("vim").unwrap();
+        let g = key(KeyCode::Char('g'), KeyModifiers::NONE);
+        let i = key(KeyCode::Char('i'), KeyModifiers::NONE);
+        assert_eq!(keymap.lookup(Mode::Normal, &[g]), Lookup::Pending);
+        assert_eq!(
+            keymap.lookup(Mode::Normal, &[g, g]),
+            Lookup::Action(Action::First)
+        );
+        assert_eq!(
+            keymap.lookup(Mode::Normal, &[g, i]),
+            Lookup::Action(Action::GotoIssue)
+        );
+        assert_eq!(keymap.lookup(Mode::Normal, &[i, g]), Lookup::None);
+        // `g` no longer goes to another issue, as it starts sequences
+        assert_eq!(keymap.keys(Action::GotoIssue), vec!["g i"]);
+        assert!(Keymap::preset("emacs").is_err());
+    }
+
+    #[test]
+    fn overrides_replace_the_keys_of_an_action() {
+        let keys = HashMap::from([(
+            "quit".to_string(),
+            KeyBinding::Many(vec!["Q".to_string(), "Ctrl+c".to_string()]),
+        )]);
+        let keymap = Keymap::default().with_overrides(&keys).unwrap();
+        assert_eq!(keymap.keys(Action::Quit), vec!["Q", "Ctrl+c"]);
+
+        let keys = HashMap::from([("fly".to_string(), KeyBinding::One("f".to_string()))]);
+        assert!(Keymap::default().with_overrides(&keys).is_err());
+        let keys = HashMap::from([("quit".to_string(), KeyBinding::One("Foo".to_string()))]);
+        assert!(Keymap::default().with_overrides(&keys).is_err());
+    }
+
+    #[test]
+    fn shift_with_letters_is_upper_case() {
+        assert_eq!(
+            Key::parse("Shift+a").unwrap(),
+            key(KeyCode::Char('A'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Key::parse("Ctrl+Shift+a").unwrap(),
+            key(KeyCode::Char('A'), KeyModifiers::CONTROL)
+        );
+        // terminals report upper case letters with or without Shift
+        assert_eq!(
+            key(KeyCode::Char('A'), KeyModifiers::SHIFT),
+            key(KeyCode::Char('A'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Key::parse("Shift+Up").unwrap(),
+            key(KeyCode::Up, KeyModifiers::SHIFT)
+        );
+    }
+
+    #[test]
+    fn shift_with_symbols_is_rejected() {
+        assert!(Key::parse("Shift+/").is_err());
+        assert!(Key::parse("Shift+1").is_err());
+    }
+
+    #[test]
+    fn lookup_depends_on_mode() {
+        let keymap = Keymap::default();
+        let n = [key(KeyCode::Char('n'), KeyModifiers::NONE)];
+        assert_eq!(
+            keymap.lookup(Mode::Normal, &n),
+            Lookup::Action(Action::SelectNone)
+        );
+        assert_eq!(
+            keymap.lookup(Mode::Prompt, &n),
+            Lookup::Action(Action::DiscardQueue)
+        );
+        assert_eq!(
+            keymap.lookup(Mode::Preview, &n),
+            Lookup::Action(Action::PreviewNextMatch)
+        );
+        assert_eq!(keymap.lookup(Mode::Help, &n), Lookup::None);
+
+        let q = [key(KeyCode::Char('q'), KeyModifiers::NONE)];
+        assert_eq!(
+            keymap.lookup(Mode::Prompt, &q),
+            Lookup::Action(Action::Quit)
+        );
+        let three = [key(KeyCode::Char('3'), KeyModifiers::NONE)];
+        assert_eq!(
+            keymap.lookup(Mode::Normal, &three),
+            Lookup::Action(Action::SwitchIssue(3))
+        );
+    }
+
+    #[test]
+    fn overrides_only_replace_keys_in_the_same_mode() {
+        let keys = HashMap::from([("details".to_string(), KeyBinding::One("n".to_string()))]);
+        let keymap = Keymap::default().with_overrides(&keys).unwrap();
+        let n = [key(KeyCode::Char('n'), KeyModifiers::NONE)];
+        assert_eq!(
+            keymap.lookup(Mode::Normal, &n),
+            Lookup::Action(Action::Details)
+        );
+        assert_eq!(
+            keymap.lookup(Mode::Preview, &n),
+            Lookup::Action(Action::PreviewNextMatch)
+        );
+        assert!(keymap.keys(Action::SelectNone).is_empty());
+    }
+
+    #[test]
+    fn hints_follow_the_overrides() {
+        let keys = HashMap::from([(
+            "restore_queue".to_string(),
+            KeyBinding::One("Enter".to_string()),
+        )]);
+        let keymap = Keymap::default().with_overrides(&keys).unwrap();
+        assert_eq!(keymap.first_key(Action::RestoreQueue).unwrap(), "Enter");
+        assert_eq!(
+            keymap
+                .hint(&[Action::RestoreQueue, Action::DiscardQueue], "Yes/No")
+                .unwrap(),
+            "Enter/n: Yes/No"
+        );
+        assert_eq!(keymap.first_key(Action::Visual), None);
+        assert_eq!(keymap.hint(&[Action::Visual], "Visual"), None);
+    }
+}
@@ -0,0 +1,60 @@
+//! Client library for listing and downloading Jira issue attachments.
+//!
+//! The [`Jira`] client fetches the attachment metadata of an issue and streams
+//! attachment content, reporting progress as [`DownloadEvent`]s through a
+//! [`tokio::sync::watch`] channel. [`download_to_file`] wraps this with the
+//! usual "write to a `.part` file, then rename" dance.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use jira_downloader::{Auth, DownloadEvent, Jira, download_to_file, format_file_size};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let jira = Jira::new(
+//!     "https://jira.example.com".to_string(),
+//!     Auth::Bearer { token: "secret".to_string() },
+//! );
+//!
+//! for att in jira.fetch_attachments("PROJ-123").await? {
+//!     println!("{} ({})", att.filename, format_file_size(att.size));
+//!
+//!     let (tx, _rx) = tokio::sync::watch::channel(DownloadEvent::Starting);
+//!     download_to_file(&jira, &att.content, att.filename.as_ref(), tx).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+
+pub mod compression;
+mod download;
+#[doc(hidden)]
+pub mod internal;
+pub mod jira;
+pub mod ratelimit;
+
+pub use compression::Compression;
+pub use download::download_to_file;
+pub use jira::{Attachment, Auth, DownloadEvent, Filter, IssueSummary, Jira, Project, Sprint};
+pub use ratelimit::RateLimiter;
+
+/// Formats a byte count as a human readable size using binary units,
+/// e.g. `1536` becomes `"1.50 KB"`.
+pub fn format_file_size(size: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB", "PB"];
+    let mut size = size as f64;
+    let mut unit_idx = 0;
+
+    while size >= 1024.0 && unit_idx < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit_idx += 1;
+    }
+
+    if unit_idx == 0 {
+        format!("{} {}", size as u64, UNITS[unit_idx])
+    } else {
+        format!("{:.2} {}", size, UNITS[unit_idx])
+    }
+}
@@ -1,26 +1,72 @@
-use std::{env::current_dir, path::PathBuf};
+use std::{collections::HashMap, env::current_dir, path::PathBuf};
 
 use anyhow::{Result, anyhow};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use config::{Config, File};
 use directories::ProjectDirs;
-use tracing::{debug, info};
+use tracing::{debug, error, info};
+
+use jira_downloader::{RateLimiter, format_file_size, internal::parse_file_size, jira};
 
 mod app;
-mod jira;
+mod browser;
+mod extract;
+mod goto;
+mod help;
+mod history;
+mod hooks;
+mod keymap;
+mod open;
+mod preview;
+mod queue;
+mod store;
+mod theme;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// Path to config file
     #[arg(short, long)]
     config: Option<PathBuf>,
-    /// Issue key to download
-    #[arg(value_name = "ISSUE")]
-    issue: String,
+    /// Issue keys to download, each shown in its own tab
+    #[arg(value_name = "ISSUE", required_unless_present_any = ["resume", "board"])]
+    issues: Vec<String>,
+    /// Id of an agile board, to download the attachments of the issues in
+    /// one of its sprints
+    #[arg(long, value_name = "ID")]
+    board: Option<u64>,
+    /// Id or name of the sprint of the board, chosen from a list if not given
+    #[arg(long, requires = "board")]
+    sprint: Option<String>,
+    /// Restore pending downloads of the last session without asking, or
+    /// download those of all issues if no issue is given
+    #[arg(long)]
+    resume: bool,
     /// Log level (error, warn, info, debug, trace)
     #[arg(short, long, default_value = "info")]
     loglevel: tracing::Level,
+    /// Maximum number of concurrent downloads [default: 3]
+    #[arg(short = 'j', long, value_name = "N")]
+    concurrent_downloads: Option<usize>,
+    /// Bandwidth limit for all downloads together, e.g. 2M [default: unlimited]
+    #[arg(long, value_name = "RATE")]
+    rate_limit: Option<String>,
+    /// Bandwidth limit for each single download, e.g. 512K [default: unlimited]
+    #[arg(long, value_name = "RATE")]
+    download_rate_limit: Option<String>,
+    /// Decompress .gz, .xz, .bz2 and .zst attachments while downloading
+    #[arg(long)]
+    decompress: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Remove files from the content store that are no longer linked from
+    /// any issue folder
+    Gc,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -28,24 +74,74 @@ struct Settings {
     base_url: String,
     user: Option<String>,
     token: Option<String>,
+    #[serde(default = "default_concurrent_downloads")]
+    concurrent_downloads: usize,
+    rate_limit: Option<String>,
+    download_rate_limit: Option<String>,
+    #[serde(default)]
+    content_store: bool,
+    #[serde(default)]
+    extract: Vec<String>,
+    #[serde(default)]
+    decompress: bool,
+    #[serde(default)]
+    hooks: Vec<HookSettings>,
+    #[serde(default)]
+    openers: HashMap<String, String>,
+    preview_size: Option<String>,
+    /// The built-in keymap, `default` or `vim`.
+    keymap: Option<String>,
+    /// Keys replacing those of the keymap, by action.
+    #[serde(default)]
+    keys: HashMap<String, keymap::KeyBinding>,
+    /// The built-in theme, `dark`, `light`, `high-contrast` or `monochrome`.
+    theme: Option<String>,
+    /// Styles replacing those of the theme, by part of the UI.
+    #[serde(default)]
+    colors: HashMap<String, String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct HookSettings {
+    pattern: String,
+    command: String,
+    /// Seconds after which the command is killed.
+    timeout: Option<u64>,
+}
+
+fn default_concurrent_downloads() -> usize {
+    3
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
 
-    let logfolder = project_directory()
+    let data_dir = project_directory()
         .map(|pdir| pdir.data_dir().to_path_buf())
         .unwrap_or_else(|| current_dir().unwrap_or_else(|_| PathBuf::from(".")));
 
     let logfile =
-        tracing_appender::rolling::daily(logfolder, format!("{}.log", env!("CARGO_PKG_NAME")));
+        tracing_appender::rolling::daily(&data_dir, format!("{}.log", env!("CARGO_PKG_NAME")));
 
     tracing_subscriber::fmt()
         .with_max_level(args.loglevel)
         .with_writer(logfile)
         .init();
 
+    let history = history::History::open(&data_dir.join("history.db"))?;
+    let content_store = store::ContentStore::new(data_dir.join("store"));
+
+    if let Some(Command::Gc) = args.command {
+        let (removed, freed) = content_store.gc(&history)?;
+        println!(
+            "Removed {} files from the content store, freeing {}.",
+            removed,
+            format_file_size(freed)
+        );
+        return Ok(());
+    }
+
     let config_builder = Config::builder();
     let config_builder = if let Some(config_path) = args.config {
         if !config_path.exists() {
@@ -84,23 +180,204 @@ async fn main() -> Result<()> {
 
     info!("Jira Base: {}, Auth: {}", settings.base_url, authdesc);
 
-    let jira = jira::Jira::new(settings.base_url, authmethod);
+    let rate_limiter = RateLimiter::new(parse_rate(
+        args.rate_limit.as_deref().or(settings.rate_limit.as_deref()),
+    )?);
+    let download_rate_limit = parse_rate(
+        args.download_rate_limit
+            .as_deref()
+            .or(settings.download_rate_limit.as_deref()),
+    )?;
 
-    let attachments = jira.fetch_attachments(&args.issue).await?;
-    for att in &attachments {
-        let formatted_size = app::format_file_size(att.size as usize);
-        info!(
-            "Attachment: \"{}\" ({}) - {}",
-            att.filename, formatted_size, att.created
-        );
+    let jira = jira::Jira::new(settings.base_url.clone(), authmethod)
+        .with_rate_limiter(rate_limiter.clone());
+
+    let download_settings = app::DownloadSettings {
+        max_downloads: args
+            .concurrent_downloads
+            .unwrap_or(settings.concurrent_downloads),
+        rate_limiter,
+        download_rate_limit,
+        content_store: settings.content_store.then_some(content_store),
+        extract: filename_patterns(&settings.extract)?,
+        decompress: args.decompress || settings.decompress,
+        hooks: settings
+            .hooks
+            .iter()
+            .map(|hook| {
+                hooks::Hook::new(
+                    &hook.pattern,
+                    hook.command.clone(),
+                    hook.timeout
+                        .map_or(hooks::DEFAULT_TIMEOUT, std::time::Duration::from_secs),
+                )
+            })
+            .collect::<Result<_>>()?,
+    };
+
+    let mut loaded = Vec::new();
+    for issue in &args.issues {
+        if loaded.iter().any(|(key, _, _)| key == issue) {
+            continue;
+        }
+        app::check_issue_key(issue)?;
+        let attachments = jira.fetch_attachments(issue).await?;
+        for att in &attachments {
+            let formatted_size = format_file_size(att.size);
+            info!(
+                "Attachment of {}: \"{}\" ({}) - {}",
+                issue, att.filename, formatted_size, att.created
+            );
+        }
+        let saved_queue = queue::SavedQueue::load(&data_dir, &settings.base_url, issue).await?;
+        // unlike the issues of a sprint, the issues given are recent ones
+        if let Err(e) = history.add_recent_issue(&settings.base_url, issue) {
+            error!("Failed to record recent issue {}: {}", issue, e);
+        }
+        loaded.push((issue.clone(), attachments, saved_queue));
+    }
+
+    if let Some(board) = args.board {
+        let sprints = jira.fetch_sprints(board).await?;
+        let sprint = choose_sprint(&sprints, args.sprint.as_deref())?;
+        info!("Sprint {} of board {}: {}", sprint.id, board, sprint.name);
+        let issues = jira.fetch_sprint_attachments(sprint.id).await?;
+        if issues.iter().all(|(_, attachments)| attachments.is_empty()) {
+            return Err(anyhow!("No attachments in sprint {}", sprint.name));
+        }
+        for (issue, attachments) in issues {
+            if attachments.is_empty() || loaded.iter().any(|(key, _, _)| *key == issue) {
+                continue;
+            }
+            app::check_issue_key(&issue)?;
+            let saved_queue =
+                queue::SavedQueue::load(&data_dir, &settings.base_url, &issue).await?;
+            loaded.push((issue, attachments, saved_queue));
+        }
     }
 
-    let mut app = app::App::new(jira, args.issue.clone(), args.issue.into(), attachments);
+    let mut loaded = loaded.into_iter();
+    let Some((issue, attachments, saved_queue)) = loaded.next() else {
+        // --resume without an issue
+        return queue::download_all(&jira, &history, &data_dir, &download_settings).await;
+    };
+
+    let mut app = app::App::new(
+        jira,
+        issue.clone(),
+        std::path::absolute(&issue)?,
+        attachments,
+        download_settings,
+        data_dir,
+        history,
+    )
+    .with_ui_settings(app::UiSettings {
+        openers: settings.openers,
+        preview_size: match settings.preview_size {
+            Some(size) => parse_file_size(&size)?,
+            None => app::UiSettings::default().preview_size,
+        },
+        keymap: keymap::Keymap::preset(settings.keymap.as_deref().unwrap_or("default"))?
+            .with_overrides(&settings.keys)?,
+        theme: load_theme(settings.theme.as_deref(), &settings.colors)?,
+    });
+    let mut saved_queues = vec![saved_queue];
+    for (issue, attachments, saved_queue) in loaded {
+        app.add_issue(issue.clone(), std::path::absolute(&issue)?, attachments);
+        saved_queues.push(saved_queue);
+    }
+    for saved_queue in saved_queues.into_iter().flatten() {
+        app.restore_queue(saved_queue, !args.resume);
+    }
     let mut terminal = ratatui::init();
-    app.run(&mut terminal).await?;
+    // the panic hook of ratatui restores the terminal, but not the mouse
+    let restore_terminal = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = crossterm::execute!(std::io::stdout(), crossterm::event::DisableMouseCapture);
+        restore_terminal(info);
+    }));
+    // restore the terminal before reporting an error
+    let result = app.run(&mut terminal).await;
     ratatui::restore();
+    result
+}
+
+/// Returns the sprint with the given id or name, or lets the user choose one
+/// of `sprints`, offering the active one.
+fn choose_sprint<'a>(
+    sprints: &'a [jira::Sprint],
+    sprint: Option<&str>,
+) -> Result<&'a jira::Sprint> {
+    if let Some(sprint) = sprint {
+        return sprints
+            .iter()
+            .find(|s| s.id.to_string() == sprint || s.name.eq_ignore_ascii_case(sprint))
+            .ok_or_else(|| anyhow!("Sprint {} not found", sprint));
+    }
+    if sprints.is_empty() {
+        return Err(anyhow!("The board has no sprints"));
+    }
 
-    Ok(())
+    let active = sprints
+        .iter()
+        .rposition(|s| s.state == "active")
+        .unwrap_or(sprints.len() - 1);
+    for (i, sprint) in sprints.iter().enumerate() {
+        println!("{:>3}. {} ({})", i + 1, sprint.name, sprint.state);
+    }
+    loop {
+        print!("Sprint [{}]: ", active + 1);
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input)? == 0 {
+            return Err(anyhow!("No sprint chosen"));
+        }
+        match input.trim() {
+            "" => return Ok(&sprints[active]),
+            input => match input.parse::<usize>() {
+                Ok(n) if (1..=sprints.len()).contains(&n) => return Ok(&sprints[n - 1]),
+                _ => println!("Enter a number between 1 and {}.", sprints.len()),
+            },
+        }
+    }
+}
+
+/// Returns the configured theme, or the monochrome one without any colors if
+/// the `NO_COLOR` environment variable is set.
+fn load_theme(name: Option<&str>, colors: &HashMap<String, String>) -> Result<theme::Theme> {
+    let theme = theme::Theme::preset(name.unwrap_or("dark"))?;
+    if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        Ok(theme::Theme::monochrome()
+            .with_colors(colors)?
+            .without_colors())
+    } else {
+        theme.with_colors(colors)
+    }
+}
+
+/// Parses a bandwidth limit in bytes per second, `0`, `none` or `unlimited`
+/// meaning no limit.
+fn parse_rate(rate: Option<&str>) -> Result<Option<u64>> {
+    match rate.map(str::trim) {
+        None | Some("none" | "unlimited") => Ok(None),
+        Some(rate) => {
+            let rate = parse_file_size(rate.trim_end_matches("/s"))?;
+            Ok(Some(rate).filter(|&r| r > 0))
+        }
+    }
+}
+
+/// Builds a case-insensitive matcher for filename glob patterns.
+fn filename_patterns(patterns: &[String]) -> Result<globset::GlobSet> {
+    let mut builder = globset::GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(
+            globset::GlobBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()?,
+        );
+    }
+    Ok(builder.build()?)
 }
 
 fn project_directory() -> Option<ProjectDirs> {
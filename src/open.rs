@@ -0,0 +1,73 @@
+use std::{path::Path, process::Stdio};
+
+use anyhow::{Result, anyhow};
+use tracing::{debug, error};
+
+/// Opens `path` in the background with `opener`, or with the default
+/// application of the desktop (e.g. through `xdg-open`) if there is none.
+///
+/// The opener is a program followed by arguments, separated by whitespace.
+/// The path is passed as its last argument.
+pub fn open_detached(path: &Path, opener: Option<&str>) -> Result<()> {
+    let Some(opener) = opener else {
+        debug!("Opening {:?}", path);
+        return open::that_detached(path).map_err(|e| anyhow!("Failed to open {:?}: {}", path, e));
+    };
+
+    debug!("Opening {:?} with {}", path, opener);
+    let mut child = command_line(opener)?
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run {}: {}", opener, e))?;
+    let opener = opener.to_string();
+    tokio::spawn(async move {
+        match child.wait().await {
+            Ok(status) if !status.success() => error!("{} exited with {}", opener, status),
+            Ok(_) => {}
+            Err(e) => error!("Failed to wait for {}: {}", opener, e),
+        }
+    });
+    Ok(())
+}
+
+/// Returns the command opening `path` in the pager given by `$PAGER`.
+pub fn pager_command(path: &Path) -> Result<tokio::process::Command> {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+    let mut command = command_line(&pager)?;
+    command.arg(path);
+    Ok(command)
+}
+
+/// Returns the command opening `path` in the editor given by `$VISUAL` or
+/// `$EDITOR`.
+pub fn editor_command(path: &Path) -> Result<tokio::process::Command> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| DEFAULT_EDITOR.to_string());
+    let mut command = command_line(&editor)?;
+    command.arg(path);
+    Ok(command)
+}
+
+#[cfg(unix)]
+const DEFAULT_PAGER: &str = "less";
+#[cfg(unix)]
+const DEFAULT_EDITOR: &str = "vi";
+#[cfg(windows)]
+const DEFAULT_PAGER: &str = "more";
+#[cfg(windows)]
+const DEFAULT_EDITOR: &str = "notepad";
+
+/// Splits a command line like `less -R` into the program and its arguments.
+fn command_line(command_line: &str) -> Result<tokio::process::Command> {
+    let mut parts = command_line.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow!("Empty command {:?}", command_line))?;
+    let mut command = tokio::process::Command::new(program);
+    command.args(parts);
+    Ok(command)
+}
@@ -0,0 +1,340 @@
+use std::ops::Range;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use jira_downloader::format_file_size;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+};
+
+use crate::{keymap::Action, theme::Theme};
+
+/// A pane showing the beginning of an attachment as text.
+#[derive(Debug)]
+pub struct Preview {
+    /// The index of the previewed attachment.
+    pub attachment_index: usize,
+    filename: String,
+    content: Content,
+    scroll: usize,
+    height: usize,
+    /// The search text being typed after `/`.
+    search_input: Option<String>,
+    search: String,
+    matches: Vec<usize>,
+    current_match: usize,
+}
+
+#[derive(Debug)]
+enum Content {
+    Loading,
+    Text { lines: Vec<String>, note: String },
+    Error(String),
+}
+
+impl Preview {
+    /// Creates a preview of an attachment whose content is still loading.
+    pub fn new(attachment_index: usize, filename: String) -> Self {
+        Self {
+            attachment_index,
+            filename,
+            content: Content::Loading,
+            scroll: 0,
+            height: 0,
+            search_input: None,
+            search: String::new(),
+            matches: Vec::new(),
+            current_match: 0,
+        }
+    }
+
+    /// Sets the loaded beginning of the attachment, which is `total` bytes
+    /// long in full.
+    pub fn set_content(&mut self, result: Result<Vec<u8>, String>, total: u64) {
+        self.content = match result {
+            Ok(data) if data.contains(&0) => {
+                Content::Error("Binary content, no preview available.".to_string())
+            }
+            Ok(data) => {
+                let note = if (data.len() as u64) < total {
+                    format!(
+                        "first {} of {}",
+                        format_file_size(data.len() as u64),
+                        format_file_size(total)
+                    )
+                } else {
+                    format_file_size(total)
+                };
+                Content::Text {
+                    lines: text_lines(&data, &self.filename),
+                    note,
+                }
+            }
+            Err(e) => Content::Error(e),
+        };
+        self.scroll = 0;
+        self.update_matches();
+    }
+
+    /// Returns whether the search is being typed.
+    pub fn is_searching(&self) -> bool {
+        self.search_input.is_some()
+    }
+
+    /// Handles a key press while the search is being typed.
+    pub fn handle_search_key(&mut self, key_evt: KeyEvent) {
+        if let Some(input) = &mut self.search_input {
+            match key_evt.code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    self.search = self.search_input.take().unwrap_or_default();
+                    self.update_matches();
+                    self.jump_to_match(true);
+                }
+                KeyCode::Esc => self.search_input = None,
+                _ => {}
+            }
+        }
+    }
+
+    /// Performs an action of the preview, returning `false` if the preview
+    /// is to be closed.
+    pub fn perform(&mut self, action: Action) -> bool {
+        let page = self.height.max(1);
+        match action {
+            Action::PreviewClose => return false,
+            Action::PreviewUp => self.scroll_to(self.scroll.saturating_sub(1)),
+            Action::PreviewDown => self.scroll_to(self.scroll + 1),
+            Action::PreviewPageUp => self.scroll_to(self.scroll.saturating_sub(page)),
+            Action::PreviewPageDown => self.scroll_to(self.scroll + page),
+            Action::PreviewTop => self.scroll_to(0),
+            Action::PreviewBottom => self.scroll_to(usize::MAX),
+            Action::PreviewSearch => self.search_input = Some(String::new()),
+            Action::PreviewNextMatch => {
+                self.current_match += 1;
+                self.jump_to_match(false);
+            }
+            Action::PreviewPreviousMatch => {
+                self.current_match = self
+                    .current_match
+                    .checked_sub(1)
+                    .unwrap_or(self.matches.len().saturating_sub(1));
+                self.jump_to_match(false);
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Scrolls up or down by a few lines, e.g. for the mouse wheel.
+    pub fn scroll_by(&mut self, down: bool) {
+        let line = if down {
+            self.scroll + SCROLL_LINES
+        } else {
+            self.scroll.saturating_sub(SCROLL_LINES)
+        };
+        self.scroll_to(line);
+    }
+
+    fn lines(&self) -> &[String] {
+        match &self.content {
+            Content::Text { lines, .. } => lines,
+            _ => &[],
+        }
+    }
+
+    fn scroll_to(&mut self, line: usize) {
+        let max = self.lines().len().saturating_sub(self.height.max(1));
+        self.scroll = line.min(max);
+    }
+
+    /// Finds the lines containing the search text.
+    fn update_matches(&mut self) {
+        self.matches = if self.search.is_empty() {
+            Vec::new()
+        } else {
+            self.lines()
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| !find_all(line, &self.search).is_empty())
+                .map(|(i, _)| i)
+                .collect()
+        };
+        self.current_match = 0;
+    }
+
+    /// Scrolls to the current match, or to the first match at or after the
+    /// top line if `from_top` is set.
+    fn jump_to_match(&mut self, from_top: bool) {
+        if self.matches.is_empty() {
+            return;
+        }
+        if from_top {
+            self.current_match = self
+                .matches
+                .iter()
+                .position(|&line| line >= self.scroll)
+                .unwrap_or(0);
+        }
+        self.current_match %= self.matches.len();
+        let line = self.matches[self.current_match];
+        if line < self.scroll || line >= self.scroll + self.height {
+            self.scroll_to(line.saturating_sub(self.height / 3));
+        }
+    }
+
+    /// Renders the preview into `area`.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.height = area.height.saturating_sub(2) as usize;
+
+        let mut title = format!("Preview: {}", self.filename);
+        if let Content::Text { note, .. } = &self.content {
+            title.push_str(&format!(" ({})", note));
+        }
+        let bottom = if let Some(input) = &self.search_input {
+            format!("/{}", input)
+        } else if !self.search.is_empty() {
+            if self.matches.is_empty() {
+                format!("\"{}\" not found", self.search)
+            } else {
+                format!(
+                    "\"{}\" {}/{} lines",
+                    self.search,
+                    self.current_match + 1,
+                    self.matches.len()
+                )
+            }
+        } else {
+            String::new()
+        };
+        let block = Block::bordered()
+            .merge_borders(ratatui::symbols::merge::MergeStrategy::Exact)
+            .border_style(theme.border)
+            .title(title)
+            .title_bottom(bottom);
+
+        let text: Vec<Line> = match &self.content {
+            Content::Loading => vec![Line::from("Loading...")],
+            Content::Error(e) => vec![Line::from(e.as_str())],
+            Content::Text { lines, .. } => {
+                let current_line = self.matches.get(self.current_match).copied();
+                lines
+                    .iter()
+                    .enumerate()
+                    .skip(self.scroll)
+                    .take(self.height)
+                    .map(|(i, line)| {
+                        let style = if current_line == Some(i) {
+                            theme.current_match
+                        } else {
+                            theme.search_match
+                        };
+                        highlight(line, &self.search, style)
+                    })
+                    .collect()
+            }
+        };
+        frame.render_widget(Paragraph::new(text).block(block), area);
+    }
+}
+
+/// The number of lines scrolled by a step of the mouse wheel.
+const SCROLL_LINES: usize = 3;
+
+/// Splits the previewed data into printable lines, pretty printing JSON.
+fn text_lines(data: &[u8], filename: &str) -> Vec<String> {
+    let text = String::from_utf8_lossy(data);
+    let looks_like_json = filename.to_ascii_lowercase().ends_with(".json")
+        || text.trim_start().starts_with(['{', '[']);
+    let text = match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(value) if looks_like_json => {
+            serde_json::to_string_pretty(&value).unwrap_or_else(|_| text.into_owned())
+        }
+        _ => text.into_owned(),
+    };
+    text.lines()
+        .map(|line| {
+            line.replace('\t', "    ")
+                .chars()
+                .map(|c| if c.is_control() { '\u{fffd}' } else { c })
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns the byte ranges of the case-insensitive occurrences of `search` in
+/// `line`.
+fn find_all(line: &str, search: &str) -> Vec<Range<usize>> {
+    if search.is_empty() {
+        return Vec::new();
+    }
+    // lower case letters may differ in length, so remember the character of
+    // `line` each byte of the lower case line belongs to
+    let mut lower = String::with_capacity(line.len());
+    let mut chars = Vec::with_capacity(line.len());
+    for (i, c) in line.char_indices() {
+        for l in c.to_lowercase() {
+            lower.push(l);
+            chars.extend(std::iter::repeat_n(i..i + c.len_utf8(), l.len_utf8()));
+        }
+    }
+    let search = search.to_lowercase();
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (start, m) in lower.match_indices(&search) {
+        let range = chars[start].start..chars[start + m.len() - 1].end;
+        // matches within the same character map to the same range
+        if ranges.last().is_none_or(|last| last.end <= range.start) {
+            ranges.push(range);
+        }
+    }
+    ranges
+}
+
+/// Returns a line with the occurrences of `search` highlighted with `style`.
+fn highlight<'a>(line: &'a str, search: &str, style: Style) -> Line<'a> {
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for range in find_all(line, search) {
+        spans.push(Span::raw(&line[pos..range.start]));
+        spans.push(Span::styled(&line[range.clone()], style));
+        pos = range.end;
+    }
+    spans.push(Span::raw(&line[pos..]));
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_all_ignores_case() {
+        assert_eq!(find_all("Error: error", "ERROR"), vec![0..5, 7..12]);
+        assert_eq!(find_all("Error", ""), Vec::<Range<usize>>::new());
+        assert_eq!(find_all("Error", "warn"), Vec::<Range<usize>>::new());
+    }
+
+    #[test]
+    fn find_all_maps_lower_case_letters_of_other_length() {
+        // the Kelvin sign is 3 bytes, its lower case `k` 1 byte, while `Ⱥ` is
+        // 2 bytes and `ⱥ` 3 bytes
+        let line = "\u{212a}ȺȺ";
+        assert_eq!(find_all(line, "ⱥ"), vec![3..5, 5..7]);
+        assert_eq!(find_all(line, "kⱥ"), vec![0..5]);
+        // `İ` lower cases to two characters
+        assert_eq!(find_all("xİx", "i"), vec![1..3]);
+    }
+
+    #[test]
+    fn highlight_splits_at_char_boundaries() {
+        let line = highlight("\u{212a}ȺȺ", "ⱥ", Style::new());
+        let spans: Vec<&str> = line.spans.iter().map(|span| &*span.content).collect();
+        assert_eq!(spans, vec!["\u{212a}", "Ⱥ", "", "Ⱥ", ""]);
+    }
+}
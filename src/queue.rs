@@ -0,0 +1,336 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use futures::StreamExt;
+use jira_downloader::{Compression, DownloadEvent, Jira, RateLimiter, format_file_size};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::watch;
+use tracing::{debug, error, info};
+
+use crate::{
+    app::{DownloadJob, DownloadSettings, check_issue_key, download_attachment},
+    extract::{ArchiveKind, extract_archive},
+    history::History,
+    hooks::run_hooks,
+};
+
+/// The pending downloads of an issue, persisted so they survive restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedQueue {
+    /// The base URL of the Jira instance the issue belongs to.
+    pub base_url: String,
+    /// The issue key.
+    pub issue: String,
+    /// The folder the attachments are downloaded to.
+    pub folder: PathBuf,
+    /// The pending downloads.
+    pub entries: Vec<QueueEntry>,
+}
+
+/// A pending download of an attachment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueEntry {
+    /// The Jira id of the attachment.
+    pub id: String,
+    /// The filename of the attachment.
+    pub filename: String,
+    /// The content URL of the attachment.
+    pub url: String,
+    /// The path the attachment is downloaded to.
+    pub path: PathBuf,
+    /// The size of the attachment in bytes.
+    pub size: u64,
+    /// The number of bytes downloaded when the queue was saved.
+    pub downloaded: u64,
+    /// The state of the download when the queue was saved.
+    pub state: QueueState,
+}
+
+/// The state of a pending download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueState {
+    /// Queued, but not started yet.
+    Queued,
+    /// Being downloaded.
+    Downloading,
+    /// Paused by the user.
+    Paused,
+}
+
+impl SavedQueue {
+    /// Loads the saved queue of `issue` on the Jira instance at `base_url`.
+    ///
+    /// Returns `None` if there is no saved queue for the issue.
+    pub async fn load(data_dir: &Path, base_url: &str, issue: &str) -> Result<Option<Self>> {
+        let path = queue_path(data_dir, base_url, issue)?;
+        let json = match tokio::fs::read(&path).await {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "Failed to read queue file {:?}: {}",
+                    path,
+                    e
+                ));
+            }
+        };
+        let queue: SavedQueue = serde_json::from_slice(&json)?;
+        Ok(Some(queue).filter(|q| q.base_url == base_url && !q.entries.is_empty()))
+    }
+
+    /// Loads all saved queues for the Jira instance at `base_url`.
+    pub async fn load_all(data_dir: &Path, base_url: &str) -> Result<Vec<Self>> {
+        let mut queues = Vec::new();
+        let mut dir = match tokio::fs::read_dir(queue_dir(data_dir, base_url)).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(queues),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = dir.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match serde_json::from_slice::<SavedQueue>(&tokio::fs::read(&path).await?) {
+                Ok(queue) if queue.base_url == base_url && !queue.entries.is_empty() => {
+                    queues.push(queue)
+                }
+                Ok(_) => {}
+                Err(e) => error!("Ignoring invalid queue file {:?}: {}", path, e),
+            }
+        }
+        Ok(queues)
+    }
+
+    /// Saves the queue, replacing an earlier saved queue of the issue.
+    ///
+    /// The saved queue is removed if there are no pending downloads left.
+    pub async fn save(&self, data_dir: &Path) -> Result<()> {
+        let path = queue_path(data_dir, &self.base_url, &self.issue)?;
+        if self.entries.is_empty() {
+            match tokio::fs::remove_file(&path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => return Ok(()),
+            }
+        }
+
+        debug!(
+            "Saving {} pending downloads of {} to {:?}",
+            self.entries.len(),
+            self.issue,
+            path
+        );
+        tokio::fs::create_dir_all(queue_dir(data_dir, &self.base_url)).await?;
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec_pretty(self)?).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+        Ok(())
+    }
+}
+
+/// Returns the folder of the saved queues of the Jira instance at
+/// `base_url`, named by a hash of it, as issue keys are only unique within
+/// an instance.
+fn queue_dir(data_dir: &Path, base_url: &str) -> PathBuf {
+    let hash: String = Sha256::digest(base_url.as_bytes())
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    data_dir.join("queues").join(hash)
+}
+
+fn queue_path(data_dir: &Path, base_url: &str, issue: &str) -> Result<PathBuf> {
+    check_issue_key(issue)?;
+    Ok(queue_dir(data_dir, base_url).join(format!("{}.json", issue)))
+}
+
+/// Downloads the pending attachments of all saved queues without the UI,
+/// printing the progress to stdout.
+///
+/// Completed downloads are removed from their queue, failed ones are kept.
+pub async fn download_all(
+    jira: &Jira,
+    history: &History,
+    data_dir: &Path,
+    settings: &DownloadSettings,
+) -> Result<()> {
+    let queues = SavedQueue::load_all(data_dir, jira.base_url()).await?;
+    if queues.is_empty() {
+        println!("No pending downloads.");
+        return Ok(());
+    }
+
+    for mut queue in queues {
+        println!(
+            "{}: resuming {} pending downloads into {:?}",
+            queue.issue,
+            queue.entries.len(),
+            queue.folder
+        );
+        tokio::fs::create_dir_all(&queue.folder).await?;
+
+        let issue = queue.issue.as_str();
+        let results: Vec<(QueueEntry, Result<()>)> = futures::stream::iter(queue.entries.clone())
+            .map(|entry| async move {
+                let result = download_entry(jira, history, issue, &entry, settings).await;
+                (entry, result)
+            })
+            .buffer_unordered(settings.max_downloads.max(1))
+            .collect()
+            .await;
+
+        for (entry, result) in results {
+            match result {
+                Ok(()) => {
+                    info!("Download finished for {}", entry.filename);
+                    println!("  ✓ {} ({})", entry.filename, format_file_size(entry.size));
+                    queue.entries.retain(|e| e.id != entry.id);
+                }
+                Err(e) => {
+                    error!("Download error for {}: {}", entry.filename, e);
+                    println!("  ✗ {}: {}", entry.filename, e);
+                }
+            }
+        }
+        queue.save(data_dir).await?;
+    }
+
+    Ok(())
+}
+
+async fn download_entry(
+    jira: &Jira,
+    history: &History,
+    issue: &str,
+    entry: &QueueEntry,
+    settings: &DownloadSettings,
+) -> Result<()> {
+    let jira = match settings.download_rate_limit {
+        Some(rate) => jira.clone().with_rate_limiter(RateLimiter::new(Some(rate))),
+        None => jira.clone(),
+    };
+    let job = DownloadJob {
+        issue: issue.to_string(),
+        attachment_id: entry.id.clone(),
+        url: entry.url.clone(),
+        file_path: entry.path.clone(),
+        filename: entry.filename.clone(),
+        size: entry.size,
+        // the path lacks the compression extension if the attachment was
+        // queued for decompression
+        compression: Compression::from_filename(&entry.filename)
+            .filter(|_| entry.path.file_name() != Some(entry.filename.as_ref())),
+    };
+    let (tx, _rx) = watch::channel(DownloadEvent::Starting);
+    download_attachment(&jira, history, settings, &job, tx).await?;
+    for hook in run_hooks(&settings.hooks, jira.base_url(), &job).await {
+        println!(
+            "  {} {}: `{}` {}",
+            if hook.success { "✓" } else { "✗" },
+            entry.filename,
+            hook.command,
+            hook.status
+        );
+    }
+
+    let filename = job
+        .file_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    if ArchiveKind::from_filename(&filename).is_some() && settings.extract.is_match(&*filename) {
+        // a failed extraction does not need the download to be repeated
+        match extract_archive(entry.path.clone(), |_, _| {}).await {
+            Ok(dir) => println!("  ⊞ {} extracted to {:?}", entry.filename, dir),
+            Err(e) => {
+                error!("Failed to extract {}: {}", entry.filename, e);
+                println!("  ✗ {}: extraction failed: {}", entry.filename, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(base_url: &str, issue: &str, filename: &str) -> SavedQueue {
+        SavedQueue {
+            base_url: base_url.to_string(),
+            issue: issue.to_string(),
+            folder: PathBuf::from(issue),
+            entries: vec![QueueEntry {
+                id: "1".to_string(),
+                filename: filename.to_string(),
+                url: format!("{}/attachment/1", base_url),
+                path: Path::new(issue).join(filename),
+                size: 1,
+                downloaded: 0,
+                state: QueueState::Queued,
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn queues_of_the_same_issue_on_other_instances_are_kept_apart() {
+        let data_dir = std::env::temp_dir().join(format!(
+            "jira-downloader-test-{}-queues",
+            std::process::id()
+        ));
+        let a = queue("https://a.example.com", "PROJ-1", "a.log");
+        let b = queue("https://b.example.com", "PROJ-1", "b.log");
+        a.save(&data_dir).await.unwrap();
+        b.save(&data_dir).await.unwrap();
+
+        let loaded = SavedQueue::load(&data_dir, &a.base_url, "PROJ-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.entries, a.entries);
+        let loaded = SavedQueue::load_all(&data_dir, &b.base_url).await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].entries, b.entries);
+
+        SavedQueue {
+            entries: Vec::new(),
+            ..a.clone()
+        }
+        .save(&data_dir)
+        .await
+        .unwrap();
+        assert!(
+            SavedQueue::load(&data_dir, &a.base_url, "PROJ-1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            SavedQueue::load(&data_dir, &b.base_url, "PROJ-1")
+                .await
+                .unwrap()
+                .is_some()
+        );
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn issue_keys_cannot_leave_the_queue_folder() {
+        let data_dir = std::env::temp_dir().join(format!(
+            "jira-downloader-test-{}-invalid-queues",
+            std::process::id()
+        ));
+        let url = "https://a.example.com";
+        assert!(SavedQueue::load(&data_dir, url, "../PROJ-1").await.is_err());
+        assert!(
+            queue(url, "../../evil", "a.log")
+                .save(&data_dir)
+                .await
+                .is_err()
+        );
+        assert!(!data_dir.exists());
+    }
+}
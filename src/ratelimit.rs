@@ -0,0 +1,163 @@
+//! Bandwidth limiting for downloads.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+/// A token bucket limiting the throughput of downloads.
+///
+/// Clones share the same bucket, so a single limiter can throttle several
+/// downloads together and its rate can be changed while they are running.
+/// A limiter without a rate does not throttle at all.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    rate: Option<u64>,
+    /// Available bytes, negative while acquirers wait for the bucket to refill.
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a new rate limiter.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - The maximum throughput in bytes per second, or `None` for no limit
+    pub fn new(rate: Option<u64>) -> Self {
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                rate: rate.filter(|&r| r > 0),
+                tokens: 0.0,
+                updated: Instant::now(),
+            })),
+        }
+    }
+
+    /// Creates a rate limiter that does not throttle.
+    pub fn unlimited() -> Self {
+        Self::new(None)
+    }
+
+    /// Returns the current limit in bytes per second, or `None` if unlimited.
+    pub fn rate(&self) -> Option<u64> {
+        self.bucket.lock().unwrap().rate
+    }
+
+    /// Changes the limit, affecting all downloads sharing this limiter.
+    ///
+    /// A rate of `None` or `Some(0)` removes the limit.
+    pub fn set_rate(&self, rate: Option<u64>) {
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.refill();
+        bucket.rate = rate.filter(|&r| r > 0);
+        bucket.tokens = bucket.tokens.min(bucket.capacity());
+    }
+
+    /// Waits until `bytes` may be transferred without exceeding the limit.
+    pub async fn acquire(&self, bytes: u64) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let Some(rate) = bucket.rate else {
+                return;
+            };
+            bucket.refill();
+            bucket.tokens -= bytes as f64;
+            if bucket.tokens >= 0.0 {
+                return;
+            }
+            Duration::from_secs_f64(-bucket.tokens / rate as f64)
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+impl Bucket {
+    /// The bucket holds at most one second worth of transfer.
+    fn capacity(&self) -> f64 {
+        self.rate.unwrap_or(0) as f64
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        if let Some(rate) = self.rate {
+            let elapsed = now.duration_since(self.updated).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * rate as f64).min(self.capacity());
+        }
+        self.updated = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_rate_is_unlimited() {
+        assert_eq!(RateLimiter::new(Some(0)).rate(), None);
+        assert_eq!(RateLimiter::default().rate(), None);
+        let limiter = RateLimiter::new(Some(100));
+        limiter.set_rate(Some(0));
+        assert_eq!(limiter.rate(), None);
+    }
+
+    #[test]
+    fn clones_share_the_rate() {
+        let limiter = RateLimiter::new(Some(100));
+        limiter.clone().set_rate(Some(200));
+        assert_eq!(limiter.rate(), Some(200));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unlimited_acquire_does_not_wait() {
+        let limiter = RateLimiter::unlimited();
+        let start = Instant::now();
+        limiter.acquire(u64::MAX).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn acquire_waits_for_the_bucket_to_refill() {
+        let limiter = RateLimiter::new(Some(10_000));
+        let start = Instant::now();
+        // the bucket starts empty
+        for _ in 0..3 {
+            limiter.acquire(1_000).await;
+        }
+        assert_eq!(start.elapsed().as_millis(), 300);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn bucket_holds_at_most_one_second() {
+        let limiter = RateLimiter::new(Some(1_000));
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        let start = Instant::now();
+        limiter.acquire(1_000).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        limiter.acquire(1_000).await;
+        assert_eq!(start.elapsed().as_millis(), 1_000);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn lowering_the_rate_drops_tokens_above_the_new_capacity() {
+        let limiter = RateLimiter::new(Some(1_000));
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        limiter.set_rate(Some(100));
+        let start = Instant::now();
+        limiter.acquire(200).await;
+        assert_eq!(start.elapsed().as_millis(), 1_000);
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tracing::{debug, info};
+
+use jira_downloader::internal::part_path;
+
+use crate::history::History;
+
+/// A content-addressed store of downloaded files.
+///
+/// Files are kept by their SHA-256 hash, and the downloaded files in the
+/// issue folders become hard links to the stored content, or reflinks on
+/// filesystems supporting them. Identical attachments of several issues thus
+/// only take up disk space once.
+#[derive(Debug, Clone)]
+pub struct ContentStore {
+    dir: PathBuf,
+}
+
+impl ContentStore {
+    /// Creates a store keeping its files in `dir`.
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Adds a downloaded file with the hash `sha256` to the store.
+    ///
+    /// If the content is stored already, the file is replaced by a link to
+    /// the stored content.
+    pub async fn add(&self, path: &Path, sha256: &str) -> Result<()> {
+        let store = self.clone();
+        let path = path.to_path_buf();
+        let sha256 = sha256.to_string();
+        tokio::task::spawn_blocking(move || store.add_blocking(&path, &sha256)).await?
+    }
+
+    fn add_blocking(&self, path: &Path, sha256: &str) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let entry = self.dir.join(sha256);
+
+        if entry.exists() {
+            debug!("Replacing {:?} by a link to {:?}", path, entry);
+            let tmp_path = part_path(path);
+            link_or_reflink(&entry, &tmp_path)?;
+            if let Err(e) = std::fs::rename(&tmp_path, path) {
+                let _ = std::fs::remove_file(&tmp_path);
+                return Err(e.into());
+            }
+        } else {
+            debug!("Storing {:?} as {:?}", path, entry);
+            let tmp_path = part_path(&entry);
+            link_or_reflink(path, &tmp_path)?;
+            std::fs::rename(&tmp_path, &entry)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the stored files that are no longer linked from any issue
+    /// folder.
+    ///
+    /// Returns the number of removed files and the disk space freed.
+    pub fn gc(&self, history: &History) -> Result<(usize, u64)> {
+        let mut removed = 0;
+        let mut freed = 0;
+
+        let dir = match std::fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((0, 0)),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in dir {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            let sha256 = entry.file_name().to_string_lossy().into_owned();
+            if !metadata.is_file() || is_linked(&metadata) || self.is_reflinked(history, &sha256)? {
+                continue;
+            }
+
+            info!("Removing unused store entry {}", sha256);
+            std::fs::remove_file(entry.path())?;
+            removed += 1;
+            freed += metadata.len();
+        }
+
+        Ok((removed, freed))
+    }
+
+    /// Checks whether a downloaded file with the given hash still exists,
+    /// which is only known from the download history for reflinked files.
+    fn is_reflinked(&self, history: &History, sha256: &str) -> Result<bool> {
+        Ok(history.find_by_hash(sha256)?.iter().any(|record| {
+            !record.path.starts_with(&self.dir)
+                && std::fs::metadata(&record.path).is_ok_and(|m| m.len() == record.size)
+        }))
+    }
+}
+
+/// Creates a hard link to `original`, or a reflink if hard links are not
+/// possible, e.g. because `link` is on another filesystem.
+fn link_or_reflink(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::fs::hard_link(original, link).or_else(|e| {
+        debug!("Hard link failed ({}), trying a reflink", e);
+        reflink_copy::reflink(original, link)
+    })
+}
+
+#[cfg(unix)]
+fn is_linked(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn is_linked(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
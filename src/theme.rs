@@ -0,0 +1,325 @@
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::{Result, anyhow};
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::app::AttachmentState;
+
+/// The styles of the parts of the UI.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    /// The column headers of tables.
+    pub header: Style,
+    /// The selected row, tab or entry.
+    pub selection: Style,
+    /// The borders of the panes.
+    pub border: Style,
+    /// The help bar at the bottom.
+    pub status_bar: Style,
+    /// The occurrences of the search in the preview.
+    pub search_match: Style,
+    /// The occurrences of the search on the current line of the preview.
+    pub current_match: Style,
+    /// Attachments that have not been downloaded.
+    pub not_downloaded: Style,
+    /// Attachments that are queued for download.
+    pub queued: Style,
+    /// Attachments that are being downloaded.
+    pub downloading: Style,
+    /// Downloads that have been paused.
+    pub paused: Style,
+    /// Attachments that have been downloaded.
+    pub downloaded: Style,
+    /// Attachments downloaded to another folder before.
+    pub available: Style,
+    /// Downloads that have failed.
+    pub failed: Style,
+    /// Archives that are being extracted.
+    pub extracting: Style,
+    /// Archives that have been extracted.
+    pub extracted: Style,
+    /// Archives whose extraction has failed.
+    pub extraction_failed: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// Colors for terminals with a dark background.
+    pub fn dark() -> Self {
+        Self {
+            header: Style::new().fg(Color::Yellow),
+            selection: Style::new().add_modifier(Modifier::REVERSED),
+            border: Style::new(),
+            status_bar: Style::new().add_modifier(Modifier::REVERSED),
+            search_match: Style::new().add_modifier(Modifier::REVERSED),
+            current_match: Style::new().fg(Color::Black).bg(Color::Yellow),
+            not_downloaded: Style::new(),
+            queued: Style::new().fg(Color::Cyan),
+            downloading: Style::new().fg(Color::LightBlue),
+            paused: Style::new().fg(Color::Yellow),
+            downloaded: Style::new().fg(Color::Green),
+            available: Style::new().fg(Color::Magenta),
+            failed: Style::new().fg(Color::LightRed),
+            extracting: Style::new().fg(Color::LightBlue),
+            extracted: Style::new().fg(Color::Green),
+            extraction_failed: Style::new().fg(Color::LightRed),
+        }
+    }
+
+    /// Colors for terminals with a light background.
+    pub fn light() -> Self {
+        Self {
+            header: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            selection: Style::new().fg(Color::Black).bg(Color::Gray),
+            border: Style::new().fg(Color::DarkGray),
+            status_bar: Style::new().fg(Color::White).bg(Color::Blue),
+            search_match: Style::new().bg(Color::Gray),
+            current_match: Style::new().fg(Color::Black).bg(Color::LightYellow),
+            not_downloaded: Style::new(),
+            queued: Style::new().fg(Color::Blue),
+            downloading: Style::new().fg(Color::Magenta),
+            paused: Style::new().fg(Color::Rgb(0x9a, 0x67, 0x00)),
+            downloaded: Style::new().fg(Color::Green),
+            available: Style::new().fg(Color::Magenta),
+            failed: Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+            extracting: Style::new().fg(Color::Magenta),
+            extracted: Style::new().fg(Color::Green),
+            extraction_failed: Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// Bright colors and bold text on black.
+    pub fn high_contrast() -> Self {
+        let bold = Style::new().add_modifier(Modifier::BOLD);
+        Self {
+            header: bold.fg(Color::White).add_modifier(Modifier::UNDERLINED),
+            selection: bold.fg(Color::Black).bg(Color::LightYellow),
+            border: Style::new().fg(Color::White),
+            status_bar: bold.fg(Color::Black).bg(Color::White),
+            search_match: bold.fg(Color::Black).bg(Color::LightCyan),
+            current_match: bold.fg(Color::Black).bg(Color::LightYellow),
+            not_downloaded: Style::new().fg(Color::White),
+            queued: bold.fg(Color::LightCyan),
+            downloading: bold.fg(Color::LightBlue),
+            paused: bold.fg(Color::LightYellow),
+            downloaded: bold.fg(Color::LightGreen),
+            available: bold.fg(Color::LightMagenta),
+            failed: bold.fg(Color::LightRed),
+            extracting: bold.fg(Color::LightBlue),
+            extracted: bold.fg(Color::LightGreen),
+            extraction_failed: bold.fg(Color::LightRed),
+        }
+    }
+
+    /// No colors, only reversed and bold text, e.g. for `NO_COLOR`.
+    pub fn monochrome() -> Self {
+        let reversed = Style::new().add_modifier(Modifier::REVERSED);
+        let bold = Style::new().add_modifier(Modifier::BOLD);
+        Self {
+            header: bold,
+            selection: reversed,
+            border: Style::new(),
+            status_bar: reversed,
+            search_match: Style::new().add_modifier(Modifier::UNDERLINED),
+            current_match: reversed,
+            not_downloaded: Style::new(),
+            queued: Style::new(),
+            downloading: Style::new(),
+            paused: Style::new(),
+            downloaded: Style::new(),
+            available: Style::new(),
+            failed: bold,
+            extracting: Style::new(),
+            extracted: Style::new(),
+            extraction_failed: bold,
+        }
+    }
+
+    /// Returns the built-in theme with the given name.
+    pub fn preset(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "dark" => Ok(Self::dark()),
+            "light" => Ok(Self::light()),
+            "high-contrast" | "high_contrast" => Ok(Self::high_contrast()),
+            "monochrome" => Ok(Self::monochrome()),
+            _ => Err(anyhow!("Unknown theme {}", name)),
+        }
+    }
+
+    /// Replaces the styles given in `colors`, a map of the parts of the UI
+    /// to styles like `bold red on black`.
+    pub fn with_colors(mut self, colors: &HashMap<String, String>) -> Result<Self> {
+        for (name, spec) in colors {
+            let style = self
+                .style_mut(name)
+                .ok_or_else(|| anyhow!("Unknown part {} in [colors]", name))?;
+            *style = parse_style(spec)?;
+        }
+        Ok(self)
+    }
+
+    /// Removes all colors, keeping bold, reversed and other text attributes.
+    pub fn without_colors(mut self) -> Self {
+        for name in STYLE_NAMES {
+            if let Some(style) = self.style_mut(name) {
+                *style = Style {
+                    fg: None,
+                    bg: None,
+                    underline_color: None,
+                    ..*style
+                };
+            }
+        }
+        self
+    }
+
+    fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        Some(match name {
+            "header" => &mut self.header,
+            "selection" => &mut self.selection,
+            "border" => &mut self.border,
+            "status_bar" => &mut self.status_bar,
+            "search_match" => &mut self.search_match,
+            "current_match" => &mut self.current_match,
+            "not_downloaded" => &mut self.not_downloaded,
+            "queued" => &mut self.queued,
+            "downloading" => &mut self.downloading,
+            "paused" => &mut self.paused,
+            "downloaded" => &mut self.downloaded,
+            "available" => &mut self.available,
+            "failed" => &mut self.failed,
+            "extracting" => &mut self.extracting,
+            "extracted" => &mut self.extracted,
+            "extraction_failed" => &mut self.extraction_failed,
+            _ => return None,
+        })
+    }
+
+    /// Returns the style of the state column of an attachment.
+    pub fn state(&self, state: &AttachmentState) -> Style {
+        match state {
+            AttachmentState::NotDownloaded => self.not_downloaded,
+            AttachmentState::Queued => self.queued,
+            AttachmentState::Downloading { .. } => self.downloading,
+            AttachmentState::Paused { .. } => self.paused,
+            AttachmentState::Downloaded => self.downloaded,
+            AttachmentState::Available { .. } => self.available,
+            AttachmentState::Failed { .. } => self.failed,
+            AttachmentState::Extracting { .. } => self.extracting,
+            AttachmentState::Extracted => self.extracted,
+            AttachmentState::ExtractionFailed { .. } => self.extraction_failed,
+        }
+    }
+}
+
+/// The parts of the UI that can be styled.
+const STYLE_NAMES: &[&str] = &[
+    "header",
+    "selection",
+    "border",
+    "status_bar",
+    "search_match",
+    "current_match",
+    "not_downloaded",
+    "queued",
+    "downloading",
+    "paused",
+    "downloaded",
+    "available",
+    "failed",
+    "extracting",
+    "extracted",
+    "extraction_failed",
+];
+
+/// Parses a style like `bold red on black`: text attributes, a foreground
+/// color and a background color after `on`, each optional.
+fn parse_style(spec: &str) -> Result<Style> {
+    let mut style = Style::new();
+    let mut words = spec.split_whitespace();
+    while let Some(word) = words.next() {
+        let modifier = match word.to_ascii_lowercase().as_str() {
+            "bold" => Modifier::BOLD,
+            "dim" => Modifier::DIM,
+            "italic" => Modifier::ITALIC,
+            "underlined" => Modifier::UNDERLINED,
+            "reversed" => Modifier::REVERSED,
+            "crossed_out" => Modifier::CROSSED_OUT,
+            "on" => {
+                let color = words
+                    .next()
+                    .ok_or_else(|| anyhow!("Missing background color in {}", spec))?;
+                style = style.bg(parse_color(color)?);
+                continue;
+            }
+            _ => {
+                style = style.fg(parse_color(word)?);
+                continue;
+            }
+        };
+        style = style.add_modifier(modifier);
+    }
+    Ok(style)
+}
+
+/// Parses a color name like `lightred`, a hex color like `#ff8800` or the
+/// index of a color in the palette of the terminal.
+fn parse_color(color: &str) -> Result<Color> {
+    Color::from_str(color).map_err(|_| anyhow!("Unknown color {}", color))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_style_with_attributes_and_colors() {
+        assert_eq!(
+            parse_style("bold red on black").unwrap(),
+            Style::new()
+                .fg(Color::Red)
+                .bg(Color::Black)
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            parse_style("Italic Underlined").unwrap(),
+            Style::new().add_modifier(Modifier::ITALIC | Modifier::UNDERLINED)
+        );
+        assert_eq!(
+            parse_style("on lightblue").unwrap(),
+            Style::new().bg(Color::LightBlue)
+        );
+        assert_eq!(parse_style("").unwrap(), Style::new());
+    }
+
+    #[test]
+    fn parse_style_with_hex_and_indexed_colors() {
+        assert_eq!(
+            parse_style("#ff8800 on 208").unwrap(),
+            Style::new()
+                .fg(Color::Rgb(0xff, 0x88, 0x00))
+                .bg(Color::Indexed(208))
+        );
+    }
+
+    #[test]
+    fn parse_style_rejects_invalid_styles() {
+        assert!(parse_style("blinking").is_err());
+        assert!(parse_style("red on").is_err());
+        assert!(parse_style("red on nocolor").is_err());
+    }
+
+    #[test]
+    fn with_colors_rejects_unknown_parts() {
+        let colors = HashMap::from([("tabs".to_string(), "red".to_string())]);
+        assert!(Theme::dark().with_colors(&colors).is_err());
+        let colors = HashMap::from([("queued".to_string(), "red".to_string())]);
+        let theme = Theme::dark().with_colors(&colors).unwrap();
+        assert_eq!(theme.queued, Style::new().fg(Color::Red));
+    }
+}